@@ -0,0 +1,27 @@
+extern crate specker;
+
+use specker::combinator::{self, TermType};
+use specker::FilePosition;
+
+fn main() {
+    let input = b"name = value # comment\nother = thing";
+    let mut cursor = FilePosition::new();
+
+    while !combinator::check_eof(&mut cursor, input) {
+        let (key, _) = combinator::expect_terminated_text(&mut cursor, input, b"=")
+            .expect("failed to read key");
+        let (value, termination) = combinator::expect_terminated_text(&mut cursor, input, b"#")
+            .expect("failed to read value");
+
+        println!(
+            "{:?} = {:?}",
+            String::from_utf8_lossy(key.trimmed().slice),
+            String::from_utf8_lossy(value.trimmed().slice)
+        );
+
+        if let TermType::Sequence = termination {
+            combinator::expect_text(&mut cursor, input).expect("failed to skip comment");
+        }
+        combinator::check_new_line(&mut cursor, input);
+    }
+}
@@ -1,6 +1,5 @@
 extern crate specker;
 
-use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
@@ -16,6 +15,7 @@ fn main() {
             marker: "##",
             var_start: "${",
             var_end: "}",
+            ..specker::Options::default()
         },
     ) {
         let spec_path = maybe_spec.unwrap_or_else(|e| {
@@ -32,7 +32,7 @@ fn main() {
             let mut file =
                 fs::File::open(&path).expect(&format!("failed to open file {:?}", &path));
 
-            if let Err(e) = item.match_contents(&mut file, &HashMap::new()) {
+            if let Err(e) = item.match_contents_default(&mut file) {
                 // print nicely formatted error
                 panic!("\n{}", specker::display_error_for_file(&path, &e));
             }
@@ -5,8 +5,11 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use error::{FilePosition, ParseError, ParseResult};
+use error::{FilePosition, LexResult, ParseError, ParseResult};
+use spec::{self, Options};
+use std::fmt;
 use std::iter::Peekable;
+use std::path::PathBuf;
 use tokens::{self, TokenRef, TokenValue, TokenValueRef};
 
 /// Top item of specification AST.
@@ -23,6 +26,10 @@ pub struct Item {
     pub params: Vec<Param>,
     /// Parsed item tokens.
     pub template: Vec<Match>,
+    /// Positions of the start of the first item token and the end of the last one.
+    pub span: (FilePosition, FilePosition),
+    /// Path of the included file that contains this item, if it was included.
+    pub origin: Option<PathBuf>,
 }
 
 /// Specification item parameter.
@@ -31,7 +38,11 @@ pub struct Param {
     /// Parameter key.
     pub key: String,
     /// Parameter value.
+    ///
+    /// Values continued on following lines are joined with `\n`.
     pub value: Option<String>,
+    /// Positions of the start of the key and the end of the value in the specification file.
+    pub span: (FilePosition, FilePosition),
 }
 
 /// Specification token.
@@ -39,28 +50,100 @@ pub struct Param {
 pub enum Match {
     /// Match one or more lines containing anything.
     MultipleLines,
+    /// Match the same lines as `MultipleLines`, and capture them into a named variable.
+    CaptureLines(String),
+    /// Match at most `max` lines containing anything.
+    ///
+    /// `..^10` is `LineRange { max: 10 }`, so that the next line must be found within the
+    /// first 10 lines after the skip. Otherwise matching fails with `ExpectedMatchWithin` at
+    /// the first line after the window. At the end of the template, the input has to end
+    /// within the window, otherwise matching fails with `ExpectedEof`.
+    LineRange { max: usize },
     /// Match a newline.
     NewLine,
     /// Match specific text.
     Text(String),
     /// Match a variable from a map that will be provided when running match.
     Var(String),
+    /// Skip up to specified number of bytes within the current line before the next token.
+    Gap(usize),
+    /// Match exactly the specified number of leading spaces.
+    Indent(usize),
+}
+
+impl Match {
+    /// Parses a single template line into tokens, the same way as a content line of a
+    /// specification.
+    ///
+    /// A param line fails with `ParseError::ExpectedDifferentToken`. A new line at the end of
+    /// the line is returned as `NewLine`.
+    pub fn parse_line(options: Options, line: &str) -> ParseResult<Vec<Match>> {
+        options.validate().map_err(|e| {
+            let pos = FilePosition::new();
+            ParseError::InvalidOptions(e).at(pos, pos)
+        })?;
+
+        let mut parser = Parser::new(tokens::tokenize(options.into(), line.as_bytes()).peekable());
+        let template = parser.parse_template()?;
+        if parser.token_iter.peek().is_some() {
+            parser.expect_template_token()?;
+        }
+
+        if options.interpret_escapes {
+            return Ok(spec::unescape_template(template));
+        }
+        Ok(template)
+    }
+}
+
+/// Writes the token as it is written in a template with the default options.
+///
+/// `Indent` is written as spaces and `NewLine` as a line break. `Gap` has no template syntax,
+/// so it is written as `<gap N>`, which does not parse back into a `Gap`.
+impl fmt::Display for Match {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Match::MultipleLines => "..".fmt(f),
+            Match::LineRange { max } => write!(f, "..^{}", max),
+            Match::CaptureLines(ref key) => write!(f, "${{..{}}}", key),
+            Match::NewLine => "\n".fmt(f),
+            Match::Text(ref text) => text.fmt(f),
+            Match::Var(ref key) => write!(f, "${{{}}}", key),
+            Match::Gap(len) => write!(f, "<gap {}>", len),
+            Match::Indent(len) => write!(f, "{:1$}", "", len),
+        }
+    }
 }
 
 /// Specification parser.
-pub struct Parser<'s> {
-    token_iter: Peekable<tokens::Iter<'s>>,
+pub struct Parser<'s, I>
+where
+    I: Iterator<Item = LexResult<TokenRef<'s>>>,
+{
+    token_iter: Peekable<I>,
     pos: FilePosition,
+    strict: bool,
 }
 
-impl<'s> Parser<'s> {
-    pub fn new(token_iter: Peekable<tokens::Iter<'s>>) -> Parser<'s> {
+impl<'s, I> Parser<'s, I>
+where
+    I: Iterator<Item = LexResult<TokenRef<'s>>>,
+{
+    pub fn new(token_iter: Peekable<I>) -> Parser<'s, I> {
         Parser {
             token_iter: token_iter,
             pos: FilePosition::new(),
+            strict: false,
         }
     }
 
+    /// Fails with `EmptyItem` at markers that are not followed by a key or a value, instead
+    /// of parsing them as params with an empty key.
+    pub fn strict(mut self) -> Parser<'s, I> {
+        self.strict = true;
+        self
+    }
+
     pub fn parse_spec(&mut self) -> ParseResult<Spec> {
         let mut items = Vec::new();
 
@@ -72,9 +155,14 @@ impl<'s> Parser<'s> {
     }
 
     fn parse_item(&mut self) -> ParseResult<Option<Item>> {
+        let lo = self.next_token_lo();
+        let params = self.parse_params()?;
+        let template = self.parse_template()?;
         let item = Item {
-            params: self.parse_params()?,
-            template: self.parse_template()?,
+            params: params,
+            template: template,
+            span: (lo, self.pos),
+            origin: None,
         };
 
         if item.params.is_empty() && item.template.is_empty() {
@@ -90,6 +178,8 @@ impl<'s> Parser<'s> {
         while self.check_next_token_is_template_item()? {
             items.push(match self.expect_template_token()? {
                 TokenValueRef::MatchAnyNumberOfLines => Match::MultipleLines,
+                TokenValueRef::MatchLinesWithin(max) => Match::LineRange { max: max },
+                TokenValueRef::CaptureLines(s) => Match::CaptureLines(s.into()),
                 TokenValueRef::MatchText(s) => Match::Text(s.into()),
                 TokenValueRef::MatchNewline => Match::NewLine,
                 TokenValueRef::Var(s) => Match::Var(s.into()),
@@ -108,14 +198,25 @@ impl<'s> Parser<'s> {
                 None => return Ok(params),
                 Some(v) => v,
             } {
+                let lo = self.next_token_lo();
                 let key = self.expect_key()?;
+                let value = if self.check_next_token_is_value()? {
+                    let mut value = String::from(self.expect_value()?);
+                    while self.check_next_token_is_value()? {
+                        value.push('\n');
+                        value.push_str(self.expect_value()?);
+                    }
+                    Some(value)
+                } else {
+                    None
+                };
+                if self.strict && key.is_empty() && value.is_none() {
+                    return Err(ParseError::EmptyItem.at(lo, self.pos));
+                }
                 params.push(Param {
                     key: key.into(),
-                    value: if self.check_next_token_is_value()? {
-                        Some(self.expect_value()?.into())
-                    } else {
-                        None
-                    },
+                    value: value,
+                    span: (lo, self.pos),
                 })
             } else {
                 break;
@@ -125,12 +226,22 @@ impl<'s> Parser<'s> {
         Ok(params)
     }
 
+    /// Returns the start of the next token, or the current position if there is none.
+    fn next_token_lo(&mut self) -> FilePosition {
+        match self.token_iter.peek() {
+            Some(&Ok(ref token)) => token.lo,
+            _ => self.pos,
+        }
+    }
+
     fn check_next_token_is_template_item(&mut self) -> ParseResult<bool> {
         Ok(match self.token_iter.peek() {
             None => false,
             Some(&Err(ref e)) => return Err(e.clone().into()),
             Some(&Ok(TokenRef { value, .. })) => match value {
                 TokenValueRef::MatchAnyNumberOfLines => true,
+                TokenValueRef::MatchLinesWithin(_) => true,
+                TokenValueRef::CaptureLines(_) => true,
                 TokenValueRef::MatchText(_) => true,
                 TokenValueRef::MatchNewline => true,
                 TokenValueRef::Var(_) => true,
@@ -166,6 +277,8 @@ impl<'s> Parser<'s> {
         self.expect_token(
             |token: TokenValueRef<'s>| match token {
                 TokenValueRef::MatchAnyNumberOfLines
+                | TokenValueRef::MatchLinesWithin(_)
+                | TokenValueRef::CaptureLines(_)
                 | TokenValueRef::MatchText(_)
                 | TokenValueRef::MatchNewline
                 | TokenValueRef::Var(_) => Some(token),
@@ -235,19 +348,18 @@ mod tests {
     use super::*;
     use tokens::{tokenize, Options};
 
-    fn default_options() -> Options<'static> {
-        Options {
-            skip_lines: b"..",
-            marker: b"##",
-            var_start: b"${",
-            var_end: b"}",
+    fn pos(line: usize, col: usize, byte: usize) -> FilePosition {
+        FilePosition {
+            line: line,
+            col: col,
+            byte: byte,
         }
     }
 
     #[test]
     fn test_parser() {
         let tokens = tokenize(
-            default_options(),
+            Options::default(),
             b"## a: x
 ..
 Hello ${ X }
@@ -271,6 +383,7 @@ ${ Y }
                             Param {
                                 key: "a".into(),
                                 value: Some("x".into()),
+                                span: (pos(0, 3, 3), pos(0, 7, 7)),
                             },
                         ],
                         template: vec![
@@ -281,16 +394,20 @@ ${ Y }
                             Match::Text("Bye".into()),
                             Match::MultipleLines,
                         ],
+                        span: (pos(0, 3, 3), pos(4, 2, 30)),
+                        origin: None,
                     },
                     Item {
                         params: vec![
                             Param {
                                 key: "a".into(),
                                 value: Some("y".into()),
+                                span: (pos(5, 3, 34), pos(5, 7, 38)),
                             },
                             Param {
                                 key: "bbbb".into(),
                                 value: None,
+                                span: (pos(6, 3, 42), pos(6, 7, 46)),
                             },
                         ],
                         template: vec![
@@ -299,6 +416,8 @@ ${ Y }
                             Match::NewLine,
                             Match::Var("Y".into()),
                         ],
+                        span: (pos(5, 3, 34), pos(8, 4, 64)),
+                        origin: None,
                     },
                 ],
             }
@@ -0,0 +1,170 @@
+// Copyright 2017 Nerijus Arlauskas
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Command line tool that matches files against specifications.
+//!
+//! ```text
+//! specker check --spec-dir DIR [--ext txt] [--base DIR] [-D key=value]...
+//! specker lint --spec-dir DIR [--ext txt]
+//! ```
+
+extern crate specker;
+
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+use std::process;
+
+const USAGE: &str = "Usage: specker check --spec-dir DIR [options]
+       specker lint --spec-dir DIR [options]
+
+`check` walks specifications in DIR and matches every item against the files
+listed in its `file` params. `lint` only parses the specifications, failing
+on markers without a key or value, and does not read any other files.
+
+Options:
+    --spec-dir DIR       directory with specification files
+    --ext EXT            specification file extension [default: txt]
+    --base DIR           directory that file params are relative to [default: spec dir]
+                         (check only)
+    --skip-lines TEXT    marker of skipped lines [default: ..]
+    --marker TEXT        prefix of param lines [default: ##]
+    --var-start TEXT     var start [default: ${]
+    --var-end TEXT       var end [default: }]
+    -D KEY=VALUE         value of a template var, can be repeated (check only)";
+
+/// Command to run.
+#[derive(PartialEq)]
+enum Command {
+    Check,
+    Lint,
+}
+
+/// Arguments of the `check` and `lint` commands.
+struct Check {
+    command: Command,
+    spec_dir: PathBuf,
+    ext: String,
+    base: Option<PathBuf>,
+    skip_lines: String,
+    marker: String,
+    var_start: String,
+    var_end: String,
+    params: HashMap<String, String>,
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Check, String> {
+    let command = match args.next() {
+        Some(ref command) if command == "check" => Command::Check,
+        Some(ref command) if command == "lint" => Command::Lint,
+        Some(ref command) if command == "--help" || command == "-h" => {
+            println!("{}", USAGE);
+            process::exit(0);
+        }
+        Some(command) => return Err(format!("unknown command {:?}", command)),
+        None => return Err("missing command".into()),
+    };
+
+    let mut spec_dir = None;
+    let mut check = Check {
+        command: command,
+        spec_dir: PathBuf::new(),
+        ext: "txt".into(),
+        base: None,
+        skip_lines: "..".into(),
+        marker: "##".into(),
+        var_start: "${".into(),
+        var_end: "}".into(),
+        params: HashMap::new(),
+    };
+
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {}", arg))?;
+        match &arg[..] {
+            "--spec-dir" => spec_dir = Some(PathBuf::from(value)),
+            "--ext" => check.ext = value,
+            "--base" => check.base = Some(PathBuf::from(value)),
+            "--skip-lines" => check.skip_lines = value,
+            "--marker" => check.marker = value,
+            "--var-start" => check.var_start = value,
+            "--var-end" => check.var_end = value,
+            "-D" => {
+                let mut parts = value.splitn(2, '=');
+                let key = parts.next().unwrap_or("");
+                match parts.next() {
+                    Some(v) if !key.is_empty() => {
+                        check.params.insert(key.into(), v.into());
+                    }
+                    _ => return Err(format!("expected KEY=VALUE, found {:?}", value)),
+                }
+            }
+            _ => return Err(format!("unknown option {:?}", arg)),
+        }
+    }
+
+    check.spec_dir = spec_dir.ok_or_else(|| String::from("missing --spec-dir"))?;
+    Ok(check)
+}
+
+/// Prints diagnostics of all failed specs and files, and returns the number of failures.
+fn run(check: &Check) -> usize {
+    let options = specker::Options {
+        skip_lines: &check.skip_lines,
+        marker: &check.marker,
+        var_start: &check.var_start,
+        var_end: &check.var_end,
+        ..specker::Options::default()
+    };
+    let base = check.base.as_ref().unwrap_or(&check.spec_dir);
+
+    let mut failures = 0;
+    let mut walk = specker::walk_spec_dir(&check.spec_dir, &check.ext, options);
+    if check.command == Command::Lint {
+        walk = walk.strict();
+    }
+    for maybe_spec in walk {
+        let spec_path = match maybe_spec {
+            Ok(spec_path) => spec_path,
+            Err(e) => {
+                eprintln!("{}\n", specker::display_error(&e));
+                failures += 1;
+                continue;
+            }
+        };
+        if check.command == Command::Lint {
+            continue;
+        }
+
+        let params = specker::OwnedParams::new(&check.params);
+        for outcome in specker::run_spec(&spec_path, base, &params) {
+            if let Err(e) = outcome.result {
+                eprintln!("{}\n", specker::display_error_for_file(&outcome.path, &e));
+                failures += 1;
+            }
+        }
+    }
+
+    failures
+}
+
+fn main() {
+    let check = match parse_args(env::args().skip(1)) {
+        Ok(check) => check,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let failures = run(&check);
+    if failures > 0 {
+        eprintln!("{} failure(s)", failures);
+        process::exit(1);
+    }
+}
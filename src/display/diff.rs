@@ -0,0 +1,150 @@
+// Copyright 2017 Nerijus Arlauskas
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use ast::Match;
+use params::ParamSource;
+
+/// Display a unified diff between template tokens and found text.
+///
+/// Template vars are replaced with values from `params`, or displayed as `${name}` if missing.
+/// Symbols that match any text are displayed as `..`. Found lines taken by symbols that match
+/// any lines are displayed as unchanged.
+pub fn display_diff<P: ParamSource + ?Sized>(
+    template: &[Match],
+    params: &P,
+    found: &str,
+) -> String {
+    let expected = expected_lines(template, params);
+    let found: Vec<&str> = found.lines().collect();
+
+    let mut sb = String::from("--- expected\n+++ found\n");
+    for line in diff_lines(&expected, &found) {
+        match line {
+            DiffLine::Same(l) => sb.push_str(&format!(" {}\n", l)),
+            DiffLine::Expected(l) => sb.push_str(&format!("-{}\n", l)),
+            DiffLine::Found(l) => sb.push_str(&format!("+{}\n", l)),
+        }
+    }
+
+    sb
+}
+
+/// Renders template tokens into lines, the same way as they are displayed in the diff.
+#[cfg(feature = "insta")]
+pub fn template_lines<P: ParamSource + ?Sized>(template: &[Match], params: &P) -> Vec<String> {
+    expected_lines(template, params)
+        .into_iter()
+        .map(|line| line.text)
+        .collect()
+}
+
+struct ExpectedLine {
+    text: String,
+    /// The line is a symbol that matches any number of found lines.
+    skip: bool,
+}
+
+impl ExpectedLine {
+    fn text(text: String) -> ExpectedLine {
+        ExpectedLine {
+            text: text,
+            skip: false,
+        }
+    }
+}
+
+fn expected_lines<P: ParamSource + ?Sized>(template: &[Match], params: &P) -> Vec<ExpectedLine> {
+    let mut lines = Vec::new();
+    let mut line = Some(String::new());
+
+    for token in template {
+        match *token {
+            Match::NewLine => {
+                lines.push(ExpectedLine::text(line.take().unwrap_or_default()));
+                line = Some(String::new());
+            }
+            Match::MultipleLines | Match::LineRange { .. } | Match::CaptureLines(_) => {
+                if let Some(text) = line.take() {
+                    if !text.is_empty() {
+                        lines.push(ExpectedLine::text(text));
+                    }
+                }
+                lines.push(ExpectedLine {
+                    text: token.to_string(),
+                    skip: true,
+                });
+            }
+            Match::Text(ref t) => line.get_or_insert_with(String::new).push_str(t),
+            Match::Var(ref key) => {
+                let line = line.get_or_insert_with(String::new);
+                match params.get(&key[..]) {
+                    Some(value) => line.push_str(value),
+                    None => line.push_str(&format!("${{{}}}", key)),
+                }
+            }
+            Match::Gap(_) => line.get_or_insert_with(String::new).push_str(&token.to_string()),
+            Match::Indent(len) => line.get_or_insert_with(String::new).push_str(&" ".repeat(len)),
+        }
+    }
+
+    if let Some(text) = line {
+        lines.push(ExpectedLine::text(text));
+    }
+
+    lines
+}
+
+enum DiffLine<'a> {
+    Same(&'a str),
+    Expected(&'a str),
+    Found(&'a str),
+}
+
+/// Diff lines using the longest common subsequence.
+///
+/// Skip lines are not displayed, but take as many found lines as possible without leaving
+/// fewer lines the same.
+fn diff_lines<'a>(expected: &'a [ExpectedLine], found: &[&'a str]) -> Vec<DiffLine<'a>> {
+    let same = |i: usize, j: usize| !expected[i].skip && expected[i].text == found[j];
+
+    let (n, m) = (expected.len(), found.len());
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if same(i, j) {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut result = Vec::with_capacity(n.max(m));
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && same(i, j) {
+            result.push(DiffLine::Same(found[j]));
+            i += 1;
+            j += 1;
+        } else if i < n && expected[i].skip {
+            if j < m && lcs[i][j + 1] >= lcs[i + 1][j] {
+                result.push(DiffLine::Same(found[j]));
+                j += 1;
+            } else {
+                i += 1;
+            }
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            result.push(DiffLine::Expected(&expected[i].text));
+            i += 1;
+        } else {
+            result.push(DiffLine::Found(found[j]));
+            j += 1;
+        }
+    }
+
+    result
+}
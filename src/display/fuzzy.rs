@@ -0,0 +1,86 @@
+// Copyright 2017 Nerijus Arlauskas
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::any::Any;
+use TemplateMatchError;
+
+/// Returns "did you mean" hint lines for errors where found text almost matches.
+///
+/// The second line marks the characters of expected text that differ.
+pub fn suggestion(desc: &dyn Any) -> Option<(String, String)> {
+    let (expected, found) = match desc.downcast_ref::<TemplateMatchError>() {
+        Some(&TemplateMatchError::ExpectedText {
+            ref expected,
+            ref found,
+            ..
+        }) => (expected, found),
+        _ => return None,
+    };
+
+    let expected: Vec<char> = expected.chars().collect();
+    let found: Vec<char> = found.chars().collect();
+    let (distance, differs) = levenshtein(&expected, &found);
+    if distance == 0 || distance > expected.len().max(found.len()) / 3 {
+        return None;
+    }
+
+    let mut hint = String::from("did you mean \"");
+    let mut marks = " ".repeat(hint.chars().count());
+    for (c, &differs) in expected.iter().zip(differs.iter()) {
+        let escaped: String = c.escape_debug().collect();
+        let mark = if differs { "^" } else { " " };
+        marks.push_str(&mark.repeat(escaped.chars().count()));
+        hint.push_str(&escaped);
+    }
+    hint.push_str("\"?");
+
+    Some((hint, marks.trim_end().to_string()))
+}
+
+/// Returns edit distance between `a` and `b`, and which chars of `a` are not kept in `b`.
+fn levenshtein(a: &[char], b: &[char]) -> (usize, Vec<bool>) {
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..a.len() + 1 {
+        for j in 1..b.len() + 1 {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            d[i][j] = (d[i - 1][j - 1] + cost)
+                .min(d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1);
+        }
+    }
+
+    // walk back the cheapest path to find changed positions
+    let mut differs = vec![false; a.len()];
+    let (mut i, mut j) = (a.len(), b.len());
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 && a[i - 1] == b[j - 1] && d[i][j] == d[i - 1][j - 1] {
+            i -= 1;
+            j -= 1;
+        } else if i > 0 && j > 0 && d[i][j] == d[i - 1][j - 1] + 1 {
+            differs[i - 1] = true;
+            i -= 1;
+            j -= 1;
+        } else if i > 0 && d[i][j] == d[i - 1][j] + 1 {
+            differs[i - 1] = true;
+            i -= 1;
+        } else {
+            // extra char in `b` is marked at the nearest char of `a`
+            if !a.is_empty() {
+                differs[i.min(a.len() - 1)] = true;
+            }
+            j -= 1;
+        }
+    }
+
+    (d[a.len()][b.len()], differs)
+}
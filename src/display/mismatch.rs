@@ -0,0 +1,64 @@
+// Copyright 2017 Nerijus Arlauskas
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use ast::Match;
+use spec::LineMismatch;
+
+/// Display the input line of a mismatch, annotated with every template token that matched
+/// and the error at the token that diverged.
+///
+/// `contents` must be the same input that was matched.
+pub fn display_line_mismatch(contents: &[u8], mismatch: &LineMismatch) -> String {
+    let line_end = contents[mismatch.line_start..]
+        .iter()
+        .position(|b| *b == b'\n' || *b == b'\r')
+        .map_or(contents.len(), |i| mismatch.line_start + i);
+    let line = String::from_utf8_lossy(&contents[mismatch.line_start..line_end]);
+
+    let num = format!("{} ", mismatch.err.lo.line + 1);
+    let mut sb = format!("{}| {}\n", num, line);
+
+    for &(token, ref range) in &mismatch.matched {
+        let col = range.start - mismatch.line_start;
+        let len = (range.end - range.start).max(1);
+        sb.push_str(&format!(
+            "{:4$}| {:5$}{} {}\n",
+            "",
+            "",
+            "^".repeat(len),
+            token_label(token),
+            num.len(),
+            col
+        ));
+    }
+
+    let col = mismatch.err.lo.byte - mismatch.line_start;
+    sb.push_str(&format!(
+        "{:3$}| {:4$}^ {}\n",
+        "",
+        "",
+        mismatch.err.desc,
+        num.len(),
+        col
+    ));
+
+    sb
+}
+
+/// Returns the token as it is written in the template.
+fn token_label(token: &Match) -> String {
+    match *token {
+        Match::Text(ref t) => format!("{:?}", t),
+        Match::Var(ref key) => format!("${{{}}}", key),
+        Match::Gap(_) => token.to_string(),
+        Match::Indent(len) => format!("indent {}", len),
+        Match::MultipleLines => String::from(".."),
+        Match::LineRange { .. } => token.to_string(),
+        Match::CaptureLines(ref key) => format!("${{..{}}}", key),
+        Match::NewLine => String::from("new line"),
+    }
+}
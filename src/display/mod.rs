@@ -5,13 +5,20 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
+mod diff;
+#[cfg(feature = "fuzzy")]
+mod fuzzy;
+mod mismatch;
+
+pub use self::diff::display_diff;
+#[cfg(feature = "insta")]
+pub use self::diff::template_lines;
+pub use self::mismatch::display_line_mismatch;
 use std::fmt;
 use std::fs;
-use std::io::BufRead;
-use std::io::BufReader;
-use std::io::Read;
+use std::io::{self, BufRead, BufReader, Read, Write};
 use std::path::Path;
-use {At, Error};
+use {At, Error, Item, ParamSource, TemplateMatchError};
 
 /// Display nice error that combines line and column info with file contents.
 pub fn display_error<E: DisplayError>(e: &E) -> String {
@@ -33,60 +40,143 @@ pub fn display_error_for_read<E: DisplayErrorForRead, I: Read>(
     e.display_error_for_read(path, input)
 }
 
+/// Write nice error that combines line and column info with file contents.
+pub fn write_error<W: Write, E: DisplayError>(output: &mut W, e: &E) -> io::Result<()> {
+    e.write_error(output)
+}
+
+/// Write nice error that combines line and column info with file contents
+/// but error itself does not have file path info.
+pub fn write_error_for_file<W: Write, E: DisplayErrorForFile>(
+    output: &mut W,
+    path: &Path,
+    e: &E,
+) -> io::Result<()> {
+    e.write_error_for_file(output, path)
+}
+
+/// Write nice error that combines line and column info with file source contents.
+pub fn write_error_for_read<W: Write, E: DisplayErrorForRead, I: Read>(
+    output: &mut W,
+    path: &Path,
+    input: &mut I,
+    e: &E,
+) -> io::Result<()> {
+    e.write_error_for_read(output, path, input)
+}
+
+/// Match item against input that has no file path, such as stdin, and display a nice error
+/// with `display_name` as the file name if contents do not match.
+pub fn match_and_display<R: Read, P: ParamSource + ?Sized>(
+    item: &Item,
+    input: &mut R,
+    params: &P,
+    display_name: &Path,
+) -> Result<(), String> {
+    let mut contents = Vec::new();
+    if let Err(e) = input.read_to_end(&mut contents) {
+        let pos = ::error::FilePosition::new();
+        return Err(TemplateMatchError::from(e).at(pos, pos).to_string());
+    }
+
+    item.match_contents(&mut &contents[..], params)
+        .map_err(|e| e.display_error_for_read(display_name, &mut &contents[..]))
+}
+
+/// Collects written error into a string.
+fn write_to_string<F>(write: F) -> String
+where
+    F: FnOnce(&mut Vec<u8>) -> io::Result<()>,
+{
+    let mut output = Vec::new();
+    write(&mut output).expect("writing to memory should not fail");
+    String::from_utf8(output).expect("error message should be valid utf8")
+}
+
 pub trait DisplayError {
-    fn display_error(&self) -> String;
+    fn write_error<W: Write>(&self, output: &mut W) -> io::Result<()>;
+
+    fn display_error(&self) -> String {
+        write_to_string(|output| self.write_error(output))
+    }
 }
 
 impl DisplayError for Error {
-    fn display_error(&self) -> String {
+    fn write_error<W: Write>(&self, output: &mut W) -> io::Result<()> {
         match *self {
-            Error::Parse { ref path, ref err } => err.display_error_for_file(path),
-            ref other => format!("{}", other),
+            Error::Parse { ref path, ref err } => err.write_error_for_file(output, path),
+            Error::Match { ref path, ref err } => err.write_error_for_file(output, path),
+            ref other => write!(output, "{}", other),
         }
     }
 }
 
 pub trait DisplayErrorForRead {
-    fn display_error_for_read<I: Read>(&self, display_file_name: &Path, path: &mut I) -> String;
+    fn write_error_for_read<W: Write, I: Read>(
+        &self,
+        output: &mut W,
+        display_file_name: &Path,
+        file: &mut I,
+    ) -> io::Result<()>;
+
+    fn display_error_for_read<I: Read>(&self, display_file_name: &Path, file: &mut I) -> String {
+        write_to_string(|output| self.write_error_for_read(output, display_file_name, file))
+    }
 }
 
 pub trait DisplayErrorForFile {
-    fn display_error_for_file(&self, path: &Path) -> String;
+    fn write_error_for_file<W: Write>(&self, output: &mut W, path: &Path) -> io::Result<()>;
+
+    fn display_error_for_file(&self, path: &Path) -> String {
+        write_to_string(|output| self.write_error_for_file(output, path))
+    }
 }
 
 impl<T> DisplayErrorForFile for At<T>
 where
-    T: fmt::Display + fmt::Debug,
+    T: fmt::Display + fmt::Debug + 'static,
 {
-    fn display_error_for_file(&self, path: &Path) -> String {
-        let mut file = fs::File::open(path).expect("failed to open file");
-
+    fn write_error_for_file<W: Write>(&self, output: &mut W, path: &Path) -> io::Result<()> {
+        // snippets are not shown for errors that span multiple lines or unreadable files
         if self.lo.line == self.hi.line {
-            // does not handle errors that span multiple lines
-            return self.display_error_for_read(path, &mut file);
+            if let Ok(mut file) = fs::File::open(path) {
+                return self.write_error_for_read(output, path, &mut file);
+            }
         }
 
-        unimplemented!("multi line errors are not implemented");
+        writeln!(output, "in {:?}", path)?;
+        write!(output, "{}", self.desc)
     }
 }
 
 impl<T> DisplayErrorForRead for At<T>
 where
-    T: fmt::Display + fmt::Debug,
+    T: fmt::Display + fmt::Debug + 'static,
 {
-    fn display_error_for_read<I: Read>(&self, display_file_name: &Path, file: &mut I) -> String {
-        let mut extra_message = None;
-
+    fn write_error_for_read<W: Write, I: Read>(
+        &self,
+        output: &mut W,
+        display_file_name: &Path,
+        file: &mut I,
+    ) -> io::Result<()> {
         let mut lines: Option<Vec<String>> = None;
 
+        // show the part of long lines around the error start
+        let window_start = if self.lo.col < MAX_LINE_LEN - 2 {
+            0
+        } else {
+            self.lo.col - (MAX_LINE_LEN - 2) / 2
+        };
+
         for (i, rd_line) in BufReader::new(file).lines().enumerate() {
             if let Ok(rd_line) = rd_line {
                 if i + 3 > self.lo.line && i <= self.lo.line {
-                    let line = if rd_line.len() > 80 {
-                        format!("{}..", &rd_line[..78])
+                    let rd_line = if i == 0 {
+                        rd_line.trim_start_matches('\u{feff}')
                     } else {
-                        rd_line.to_string()
+                        &rd_line
                     };
+                    let line = truncate_line(rd_line, window_start);
                     if let Some(ref mut lines) = lines {
                         lines.push(line);
                     } else {
@@ -95,70 +185,87 @@ where
                 }
             }
         }
-        if let None = lines {
-            lines = Some(vec![String::from("")]);
+        let lines = lines.unwrap_or_else(|| vec![String::from("")]);
+
+        writeln!(output, "in {:?}", display_file_name)?;
+
+        // print lines
+
+        // the last line has the widest number
+        let lines_len = lines.len();
+        let num_len = format!("{} ", self.lo.line + 1).len();
+        for (i, line) in lines.into_iter().enumerate() {
+            let num = format!("{} ", self.lo.line + i + 2 - lines_len);
+
+            writeln!(output, "{:>2$}| {}", num, line, num_len)?;
         }
 
-        if let Some(lines) = lines {
-            let mut sb = String::new();
+        // print arrow
 
-            // print lines
+        let window_end = window_start + MAX_LINE_LEN - 2;
+        let col = self.lo.col - window_start + if window_start > 0 {
+            TRUNCATED.chars().count()
+        } else {
+            0
+        };
 
-            let lines_len = lines.len();
-            let mut num_len = 0;
-            for (i, line) in lines.into_iter().enumerate() {
-                let num = format!("{} ", self.lo.line + i + 2 - lines_len);
-                num_len = num.len();
+        // zero-width errors and ends before the start still get a single caret
+        let carets = if self.hi == self.lo {
+            1
+        } else {
+            self.hi.col.min(window_end).saturating_sub(self.lo.col).max(1)
+        };
 
-                sb.push_str(&num);
-                sb.push_str("| ");
-                sb.push_str(&line);
-                sb.push_str("\n");
-            }
+        write!(output, "{:1$}| ", "", num_len)?;
+        writeln!(output, "{:1$}{2}", "", col, "^".repeat(carets))?;
 
-            // print arrow
+        // print message
 
-            for _ in 0..num_len {
-                sb.push_str(" ");
-            }
-            sb.push_str("| ");
+        write!(output, "{:1$}| ", "", num_len)?;
+        write!(output, "{:1$}{2}", "", col, self.desc)?;
 
-            for _ in 0..self.lo.col {
-                sb.push_str(" ");
-            }
-            sb.push_str("^");
-            for _ in self.lo.col + 1..self.hi.col {
-                sb.push_str("^");
+        #[cfg(feature = "fuzzy")]
+        {
+            if let Some((hint, marks)) = fuzzy::suggestion(&self.desc) {
+                write!(output, "\n{:1$}| ", "", num_len)?;
+                write!(output, "{:1$}{2}", "", col, hint)?;
+                write!(output, "\n{:1$}| ", "", num_len)?;
+                write!(output, "{:1$}{2}", "", col, marks)?;
             }
+        }
 
-            sb.push_str("\n");
+        Ok(())
+    }
+}
 
-            // print message
+/// Lines longer than this are truncated in error snippets.
+const MAX_LINE_LEN: usize = 80;
 
-            for _ in 0..num_len {
-                sb.push_str(" ");
-            }
-            sb.push_str("| ");
+/// Marks the side of a line that was truncated.
+const TRUNCATED: &str = "…";
 
-            for _ in 0..self.lo.col {
-                sb.push_str(" ");
-            }
-            sb.push_str(&format!("{}", self.desc));
+/// Returns the part of line that starts at `start` byte, marking truncated sides.
+fn truncate_line(line: &str, start: usize) -> String {
+    if start == 0 && line.len() <= MAX_LINE_LEN {
+        return line.to_string();
+    }
 
-            extra_message = Some(sb);
-        }
+    let mut lo = start.min(line.len());
+    while !line.is_char_boundary(lo) {
+        lo += 1;
+    }
+    let mut hi = (start + MAX_LINE_LEN - 2).min(line.len());
+    while !line.is_char_boundary(hi) {
+        hi -= 1;
+    }
 
-        if let Some(extra_message) = extra_message {
-            format!("in {:?}\n{}", display_file_name, extra_message)
-        } else {
-            if self.lo == self.hi {
-                format!("{} in {:?} at {}", &self.desc, display_file_name, self.lo)
-            } else {
-                format!(
-                    "{} in {:?} at {} - {}",
-                    &self.desc, display_file_name, self.lo, self.hi
-                )
-            }
-        }
+    let mut result = String::new();
+    if lo > 0 {
+        result.push_str(TRUNCATED);
+    }
+    result.push_str(&line[lo..hi.max(lo)]);
+    if hi < line.len() {
+        result.push_str(TRUNCATED);
     }
+    result
 }
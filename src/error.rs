@@ -5,8 +5,10 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
+use line_ending::LineEnding;
 use std::error::Error;
 use std::fmt;
+use std::ops::{Range, RangeInclusive};
 use std::result;
 use std::str;
 use tokens::TokenValue;
@@ -16,6 +18,8 @@ use tokens::TokenValue;
 pub enum LexError {
     ExpectedSequenceFoundNewline { expected: Vec<u8> },
     ExpectedNewline,
+    UnterminatedQuote,
+    ExpectedLineCount,
     Utf8(str::Utf8Error),
 }
 
@@ -24,9 +28,18 @@ impl ::std::error::Error for LexError {
         match *self {
             LexError::ExpectedSequenceFoundNewline { .. } => "expected sequence, found newline",
             LexError::ExpectedNewline => "expected newline",
+            LexError::UnterminatedQuote => "unterminated quoted value",
+            LexError::ExpectedLineCount => "expected number of lines",
             LexError::Utf8(ref e) => e.description(),
         }
     }
+
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            LexError::Utf8(ref e) => Some(e),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for LexError {
@@ -38,6 +51,8 @@ impl fmt::Display for LexError {
                 String::from_utf8_lossy(expected)
             ),
             LexError::ExpectedNewline => "Expected new line".fmt(f),
+            LexError::UnterminatedQuote => "Expected closing quote of the value".fmt(f),
+            LexError::ExpectedLineCount => "Expected number of lines to skip".fmt(f),
             LexError::Utf8(e) => e.fmt(f),
         }
     }
@@ -59,11 +74,53 @@ impl From<str::Utf8Error> for LexError {
     }
 }
 
+/// Invalid specification parser options.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum OptionsError {
+    /// Option is empty.
+    Empty(&'static str),
+    /// Option contains a new line character.
+    NewLine(&'static str),
+    /// Option is a prefix of another option, so the lexer can not tell them apart.
+    Overlap {
+        prefix: &'static str,
+        other: &'static str,
+    },
+}
+
+impl ::std::error::Error for OptionsError {
+    fn description(&self) -> &str {
+        match *self {
+            OptionsError::Empty(_) => "option can not be empty",
+            OptionsError::NewLine(_) => "option can not contain new lines",
+            OptionsError::Overlap { .. } => "option overlaps with another option",
+        }
+    }
+}
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            OptionsError::Empty(name) => write!(f, "Option {:?} can not be empty", name),
+            OptionsError::NewLine(name) => {
+                write!(f, "Option {:?} can not contain new line characters", name)
+            }
+            OptionsError::Overlap { prefix, other } => write!(
+                f,
+                "Option {:?} can not be a prefix of option {:?}",
+                prefix, other
+            ),
+        }
+    }
+}
+
 /// Spec parser error.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum ParseError {
     Lex(LexError),
+    InvalidOptions(OptionsError),
     ExpectedKeyFoundValue,
+    EmptyItem,
     UnexpectedEndOfTokens,
     ExpectedDifferentToken {
         expected: Vec<TokenValue>,
@@ -75,11 +132,21 @@ impl ::std::error::Error for ParseError {
     fn description(&self) -> &str {
         match *self {
             ParseError::Lex(ref e) => e.description(),
+            ParseError::InvalidOptions(ref e) => e.description(),
             ParseError::ExpectedKeyFoundValue => "expected key, found value",
+            ParseError::EmptyItem => "marker without key",
             ParseError::UnexpectedEndOfTokens => "unexpected end of tokens",
             ParseError::ExpectedDifferentToken { .. } => "expected different token",
         }
     }
+
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            ParseError::Lex(ref e) => Some(e),
+            ParseError::InvalidOptions(ref e) => Some(e),
+            _ => None,
+        }
+    }
 }
 
 impl From<At<LexError>> for At<ParseError> {
@@ -92,7 +159,9 @@ impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             ParseError::Lex(ref e) => e.fmt(f),
+            ParseError::InvalidOptions(ref e) => e.fmt(f),
             ParseError::ExpectedKeyFoundValue => "Expected key, found value".fmt(f),
+            ParseError::EmptyItem => "Expected key after marker".fmt(f),
             ParseError::UnexpectedEndOfTokens => "Unexpected end of file".fmt(f),
             ParseError::ExpectedDifferentToken {
                 ref expected,
@@ -164,6 +233,14 @@ impl ::std::error::Error for TemplateWriteError {
             TemplateWriteError::Io(ref e) => e.description(),
         }
     }
+
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            TemplateWriteError::TemplateIsNotValidUtf8(ref e) => Some(e),
+            TemplateWriteError::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for TemplateWriteError {
@@ -192,9 +269,25 @@ impl From<::std::io::Error> for TemplateWriteError {
 pub enum TemplateMatchError {
     ExpectedEof,
     ExpectedEol,
-    ExpectedText { expected: String, found: String },
-    ExpectedTextFoundEof(String),
+    ExpectedText {
+        expected: String,
+        found: String,
+        found_line: String,
+    },
+    ExpectedTextFoundEof { expected: String, found: String },
+    BlockMismatch {
+        expected: Vec<String>,
+        found: Vec<String>,
+        first_line: usize,
+    },
+    ExpectedIndent { expected: usize, found: String },
+    ExpectedSkippedLine,
+    ExpectedMatchWithin { max: usize },
     MissingParam(String),
+    InvalidIgnorePattern { pattern: String, message: String },
+    InputTooLarge { limit: usize },
+    WrongLineEnding { expected: LineEnding, found: LineEnding },
+    WrongTrailingNewline { expected: bool },
     Io(::std::io::Error),
 }
 
@@ -213,24 +306,84 @@ impl PartialEq for TemplateMatchError {
         match (self, other) {
             (&TemplateMatchError::ExpectedEof, &TemplateMatchError::ExpectedEof) => true,
             (&TemplateMatchError::ExpectedEol, &TemplateMatchError::ExpectedEol) => true,
+            (
+                &TemplateMatchError::ExpectedSkippedLine,
+                &TemplateMatchError::ExpectedSkippedLine,
+            ) => true,
             (
                 &TemplateMatchError::ExpectedText {
                     expected: ref expected_a,
                     found: ref found_a,
+                    found_line: ref found_line_a,
                 },
                 &TemplateMatchError::ExpectedText {
                     expected: ref expected_b,
                     found: ref found_b,
+                    found_line: ref found_line_b,
+                },
+            ) => expected_a.eq(expected_b) && found_a.eq(found_b) && found_line_a.eq(found_line_b),
+            (
+                &TemplateMatchError::ExpectedTextFoundEof {
+                    expected: ref expected_a,
+                    found: ref found_a,
+                },
+                &TemplateMatchError::ExpectedTextFoundEof {
+                    expected: ref expected_b,
+                    found: ref found_b,
                 },
             ) => expected_a.eq(expected_b) && found_a.eq(found_b),
             (
-                &TemplateMatchError::ExpectedTextFoundEof(ref a),
-                &TemplateMatchError::ExpectedTextFoundEof(ref b),
-            ) => a.eq(b),
+                &TemplateMatchError::BlockMismatch {
+                    expected: ref expected_a,
+                    found: ref found_a,
+                    first_line: first_line_a,
+                },
+                &TemplateMatchError::BlockMismatch {
+                    expected: ref expected_b,
+                    found: ref found_b,
+                    first_line: first_line_b,
+                },
+            ) => expected_a.eq(expected_b) && found_a.eq(found_b) && first_line_a == first_line_b,
+            (
+                &TemplateMatchError::ExpectedIndent {
+                    expected: expected_a,
+                    found: ref found_a,
+                },
+                &TemplateMatchError::ExpectedIndent {
+                    expected: expected_b,
+                    found: ref found_b,
+                },
+            ) => expected_a == expected_b && found_a.eq(found_b),
+            (
+                &TemplateMatchError::ExpectedMatchWithin { max: a },
+                &TemplateMatchError::ExpectedMatchWithin { max: b },
+            ) => a == b,
             (
                 &TemplateMatchError::MissingParam(ref a),
                 &TemplateMatchError::MissingParam(ref b),
             ) => a.eq(b),
+            (
+                &TemplateMatchError::InvalidIgnorePattern { pattern: ref a, .. },
+                &TemplateMatchError::InvalidIgnorePattern { pattern: ref b, .. },
+            ) => a.eq(b),
+            (
+                &TemplateMatchError::InputTooLarge { limit: a },
+                &TemplateMatchError::InputTooLarge { limit: b },
+            ) => a == b,
+            (
+                &TemplateMatchError::WrongLineEnding {
+                    expected: expected_a,
+                    found: found_a,
+                },
+                &TemplateMatchError::WrongLineEnding {
+                    expected: expected_b,
+                    found: found_b,
+                },
+            ) => expected_a == expected_b && found_a == found_b,
+            (
+                &TemplateMatchError::WrongTrailingNewline { expected: a },
+                &TemplateMatchError::WrongTrailingNewline { expected: b },
+            ) => a == b,
             (&TemplateMatchError::Io(ref a), &TemplateMatchError::Io(ref b)) => {
                 a.description() == b.description()
             }
@@ -247,11 +400,26 @@ impl ::std::error::Error for TemplateMatchError {
             TemplateMatchError::ExpectedEof => "expected end of file",
             TemplateMatchError::ExpectedEol => "expected end of line",
             TemplateMatchError::ExpectedText { .. } => "expected text not found",
-            TemplateMatchError::ExpectedTextFoundEof(_) => "expected text, found end of file",
+            TemplateMatchError::ExpectedTextFoundEof { .. } => "expected text, found end of file",
+            TemplateMatchError::BlockMismatch { .. } => "expected lines not found",
+            TemplateMatchError::ExpectedIndent { .. } => "expected different indentation",
+            TemplateMatchError::ExpectedSkippedLine => "expected at least one line to skip",
+            TemplateMatchError::ExpectedMatchWithin { .. } => "expected match within skipped lines",
             TemplateMatchError::MissingParam(_) => "missing template param",
+            TemplateMatchError::InvalidIgnorePattern { .. } => "invalid ignore line pattern",
+            TemplateMatchError::InputTooLarge { .. } => "input is too large",
+            TemplateMatchError::WrongLineEnding { .. } => "wrong line ending",
+            TemplateMatchError::WrongTrailingNewline { .. } => "wrong new lines at end of file",
             TemplateMatchError::Io(ref e) => e.description(),
         }
     }
+
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            TemplateMatchError::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for TemplateMatchError {
@@ -262,11 +430,53 @@ impl fmt::Display for TemplateMatchError {
             TemplateMatchError::ExpectedText {
                 ref expected,
                 ref found,
+                ..
             } => write!(f, "Expected {:?}, found {:?}", expected, found),
-            TemplateMatchError::ExpectedTextFoundEof(ref p) => {
-                write!(f, "Expected {:?}, found end of file", p)
+            TemplateMatchError::ExpectedTextFoundEof {
+                ref expected,
+                ref found,
+            } => if found.is_empty() {
+                write!(f, "Expected {:?}, found end of file", expected)
+            } else {
+                write!(f, "Expected {:?}, found end of file after {:?}", expected, found)
+            },
+            TemplateMatchError::BlockMismatch {
+                ref expected,
+                ref found,
+                first_line,
+            } => write!(
+                f,
+                "Expected lines {:?} from line {}, found {:?}",
+                expected,
+                first_line + 1,
+                found
+            ),
+            TemplateMatchError::ExpectedIndent {
+                expected,
+                ref found,
+            } => write!(f, "Expected indentation of {} spaces, found {:?}", expected, found),
+            TemplateMatchError::ExpectedSkippedLine => {
+                "Expected at least one line matched by the skip symbol".fmt(f)
+            }
+            TemplateMatchError::ExpectedMatchWithin { max } => {
+                write!(f, "Expected a match within {} skipped lines", max)
             }
             TemplateMatchError::MissingParam(ref p) => write!(f, "Missing template param {:?}", p),
+            TemplateMatchError::InvalidIgnorePattern {
+                ref pattern,
+                ref message,
+            } => write!(f, "Invalid ignore line pattern {:?}: {}", pattern, message),
+            TemplateMatchError::InputTooLarge { limit } => {
+                write!(f, "Input is larger than {} bytes", limit)
+            }
+            TemplateMatchError::WrongLineEnding { expected, found } => {
+                write!(f, "Expected {}, found {}", expected, found)
+            }
+            TemplateMatchError::WrongTrailingNewline { expected } => if expected {
+                "Expected a single new line at the end of file".fmt(f)
+            } else {
+                "Expected no new line at the end of file".fmt(f)
+            },
             TemplateMatchError::Io(ref e) => e.fmt(f),
         }
     }
@@ -295,6 +505,16 @@ where
 }
 
 impl<T: fmt::Debug> At<T> {
+    /// Returns the range of bytes that this error is pointing at.
+    pub fn byte_range(&self) -> Range<usize> {
+        self.lo.byte..self.hi.byte
+    }
+
+    /// Returns the range of lines that this error is pointing at.
+    pub fn line_range(&self) -> RangeInclusive<usize> {
+        self.lo.line..=self.hi.line
+    }
+
     pub fn assert_matches(
         &self,
         other_err: &T,
@@ -347,6 +567,10 @@ where
     fn description(&self) -> &str {
         self.desc.description()
     }
+
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.desc.source()
+    }
 }
 
 impl<T: fmt::Debug> PartialEq for At<T>
@@ -371,7 +595,9 @@ where
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
 pub struct FilePosition {
     /// 0-based line of this position.
     pub line: usize,
@@ -390,6 +616,22 @@ impl FilePosition {
         }
     }
 
+    /// Returns the first position in contents, skipping the UTF-8 byte order mark.
+    ///
+    /// The byte order mark is not counted as a column.
+    pub fn start_of(contents: &[u8]) -> FilePosition {
+        let mut pos = FilePosition::new();
+        if contents.starts_with(UTF8_BOM) {
+            pos.byte = UTF8_BOM.len();
+        }
+        pos
+    }
+
+    /// Returns line, col and byte of this position.
+    pub fn as_tuple(&self) -> (usize, usize, usize) {
+        (self.line, self.col, self.byte)
+    }
+
     pub fn advance(&mut self, bytes: usize) {
         self.byte += bytes;
         self.col += bytes;
@@ -0,0 +1,94 @@
+// Copyright 2017 Nerijus Arlauskas
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Matching of spec items against files selected by glob patterns.
+
+use Result;
+use error::{At, FilePosition, TemplateMatchError};
+use globset::GlobBuilder;
+use params::ParamSource;
+use spec_path::SpecPath;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::result;
+use walkdir::WalkDir;
+
+impl SpecPath {
+    /// Matches every item against every file its `file` params expand to.
+    ///
+    /// Each `file` value is a glob pattern, such as `**/*.rs`, matched against the paths of
+    /// files under `base_dir`, relative to it. `*` does not match `/`, but `**` does. A plain
+    /// file path is a pattern that matches only itself.
+    ///
+    /// Returns the result for each matched file. If a file is matched by several items, the
+    /// first failure is kept. A pattern that matches no files is not an empty success: it is
+    /// resolved relative to `base_dir` and fails with a `NotFound` io error, the same way
+    /// `run_spec` reports a missing file. Invalid patterns fail with `Error::Glob`.
+    pub fn match_all<P: ParamSource + ?Sized>(
+        &self,
+        base_dir: &Path,
+        params: &P,
+    ) -> Result<BTreeMap<PathBuf, result::Result<(), At<TemplateMatchError>>>> {
+        let mut paths = Vec::new();
+        for entry in WalkDir::new(base_dir).sort_by(|a, b| a.file_name().cmp(b.file_name())) {
+            let entry = entry?;
+            if entry.file_type().is_file() {
+                paths.push(entry.path().to_path_buf());
+            }
+        }
+
+        let mut results = BTreeMap::new();
+
+        for (item, files) in self.items_with_all_files() {
+            let compiled = item.compile();
+            for file in files {
+                let matcher = GlobBuilder::new(file)
+                    .literal_separator(true)
+                    .build()?
+                    .compile_matcher();
+
+                let mut found = false;
+                for path in &paths {
+                    if !matcher.is_match(path.strip_prefix(base_dir)?) {
+                        continue;
+                    }
+                    found = true;
+                    let result = match File::open(path) {
+                        Ok(mut input) => compiled.match_contents(&mut input, params),
+                        Err(e) => Err(at_start(e)),
+                    };
+                    insert_result(&mut results, path.clone(), result);
+                }
+
+                if !found {
+                    let e = io::Error::new(io::ErrorKind::NotFound, "pattern matches no files");
+                    insert_result(&mut results, base_dir.join(file), Err(at_start(e)));
+                }
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+fn at_start(e: io::Error) -> At<TemplateMatchError> {
+    let pos = FilePosition::new();
+    TemplateMatchError::from(e).at(pos, pos)
+}
+
+fn insert_result(
+    results: &mut BTreeMap<PathBuf, result::Result<(), At<TemplateMatchError>>>,
+    path: PathBuf,
+    result: result::Result<(), At<TemplateMatchError>>,
+) {
+    let existing = results.entry(path).or_insert(Ok(()));
+    if existing.is_ok() {
+        *existing = result;
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright 2017 Nerijus Arlauskas
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use error::{At, FilePosition, TemplateMatchError};
+use regex::bytes::Regex;
+use std::fmt;
+
+/// Input contents with ignored lines removed.
+///
+/// Keeps enough information to map positions in filtered contents back to the original input.
+pub struct IgnoredLines {
+    contents: Vec<u8>,
+    /// Byte offset in filtered contents and the original position for every kept line.
+    lines: Vec<(usize, FilePosition)>,
+    /// The end of original input.
+    end: FilePosition,
+}
+
+impl IgnoredLines {
+    /// Removes lines that match any of the `patterns`.
+    pub fn filter(input: &[u8], patterns: &[String]) -> Result<IgnoredLines, TemplateMatchError> {
+        let mut regexes = Vec::with_capacity(patterns.len());
+        for pattern in patterns {
+            regexes.push(Regex::new(pattern).map_err(|e| {
+                TemplateMatchError::InvalidIgnorePattern {
+                    pattern: pattern.clone(),
+                    message: e.to_string(),
+                }
+            })?);
+        }
+
+        let mut contents = Vec::with_capacity(input.len());
+        let mut lines = Vec::new();
+        let mut pos = FilePosition::start_of(input);
+        let mut last_line_ignored = false;
+
+        while pos.byte < input.len() {
+            let (line_len, newline_len) = line_at(input, pos.byte);
+            let line = &input[pos.byte..pos.byte + line_len];
+
+            last_line_ignored = regexes.iter().any(|r| r.is_match(line));
+            if !last_line_ignored {
+                lines.push((contents.len(), pos));
+                contents.extend_from_slice(&input[pos.byte..pos.byte + line_len + newline_len]);
+            }
+
+            pos.advance(line_len);
+            if newline_len == 0 {
+                break;
+            }
+            pos.next_line(newline_len);
+        }
+
+        // ignored last line took the newline of previous line with it
+        if last_line_ignored && !input.ends_with(b"\n") && !input.ends_with(b"\r") {
+            if contents.ends_with(b"\r\n") {
+                contents.truncate(contents.len() - 2);
+            } else if contents.ends_with(b"\n") || contents.ends_with(b"\r") {
+                contents.truncate(contents.len() - 1);
+            }
+        }
+
+        Ok(IgnoredLines {
+            contents: contents,
+            lines: lines,
+            end: pos,
+        })
+    }
+
+    /// Filtered contents.
+    pub fn contents(&self) -> &[u8] {
+        &self.contents
+    }
+
+    /// Maps error positions in filtered contents to positions in the original input.
+    pub fn remap<T: fmt::Debug>(&self, err: At<T>) -> At<T> {
+        At {
+            lo: self.original_position(err.lo),
+            hi: self.original_position(err.hi),
+            desc: err.desc,
+        }
+    }
+
+    fn original_position(&self, pos: FilePosition) -> FilePosition {
+        if pos.byte >= self.contents.len() {
+            return self.end;
+        }
+
+        let index = match self.lines.binary_search_by(|&(start, _)| start.cmp(&pos.byte)) {
+            Ok(index) => index,
+            Err(index) => index - 1,
+        };
+        let (start, line_pos) = self.lines[index];
+        line_pos.advanced(pos.byte - start)
+    }
+}
+
+/// Returns the length of line at `start` and the length of its new line sequence.
+fn line_at(input: &[u8], start: usize) -> (usize, usize) {
+    let mut end = start;
+    while end < input.len() {
+        if input[end..].starts_with(b"\r\n") {
+            return (end - start, 2);
+        }
+        if input[end] == b'\n' || input[end] == b'\r' {
+            return (end - start, 1);
+        }
+        end += 1;
+    }
+    (end - start, 0)
+}
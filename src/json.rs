@@ -0,0 +1,148 @@
+// Copyright 2017 Nerijus Arlauskas
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Serialization of errors for machine consumption.
+//!
+//! Errors are serialized as objects with the error `kind`, `lo` and `hi` positions, the
+//! formatted `message`, and any fields of the error itself, such as `expected` and `found`.
+
+use error::{At, FilePosition, ParseError, TemplateMatchError};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json;
+
+impl Serialize for FilePosition {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(3))?;
+        map.serialize_entry("line", &self.line)?;
+        map.serialize_entry("col", &self.col)?;
+        map.serialize_entry("byte", &self.byte)?;
+        map.end()
+    }
+}
+
+impl Serialize for At<TemplateMatchError> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        let kind = match self.desc {
+            TemplateMatchError::ExpectedEof => "expected_eof",
+            TemplateMatchError::ExpectedEol => "expected_eol",
+            TemplateMatchError::ExpectedText { .. } => "expected_text",
+            TemplateMatchError::ExpectedTextFoundEof { .. } => "expected_text_found_eof",
+            TemplateMatchError::BlockMismatch { .. } => "block_mismatch",
+            TemplateMatchError::ExpectedIndent { .. } => "expected_indent",
+            TemplateMatchError::ExpectedSkippedLine => "expected_skipped_line",
+            TemplateMatchError::ExpectedMatchWithin { .. } => "expected_match_within",
+            TemplateMatchError::MissingParam(_) => "missing_param",
+            TemplateMatchError::InvalidIgnorePattern { .. } => "invalid_ignore_pattern",
+            TemplateMatchError::InputTooLarge { .. } => "input_too_large",
+            TemplateMatchError::WrongLineEnding { .. } => "wrong_line_ending",
+            TemplateMatchError::WrongTrailingNewline { .. } => "wrong_trailing_newline",
+            TemplateMatchError::Io(_) => "io",
+        };
+        map.serialize_entry("kind", kind)?;
+        map.serialize_entry("lo", &self.lo)?;
+        map.serialize_entry("hi", &self.hi)?;
+        map.serialize_entry("message", &self.desc.to_string())?;
+        match self.desc {
+            TemplateMatchError::ExpectedText {
+                ref expected,
+                ref found,
+                ref found_line,
+            } => {
+                map.serialize_entry("expected", expected)?;
+                map.serialize_entry("found", found)?;
+                map.serialize_entry("found_line", found_line)?;
+            }
+            TemplateMatchError::ExpectedTextFoundEof {
+                ref expected,
+                ref found,
+            } => {
+                map.serialize_entry("expected", expected)?;
+                map.serialize_entry("found", found)?;
+            }
+            TemplateMatchError::BlockMismatch {
+                ref expected,
+                ref found,
+                first_line,
+            } => {
+                map.serialize_entry("expected", expected)?;
+                map.serialize_entry("found", found)?;
+                map.serialize_entry("first_line", &first_line)?;
+            }
+            TemplateMatchError::ExpectedIndent {
+                expected,
+                ref found,
+            } => {
+                map.serialize_entry("expected", &expected)?;
+                map.serialize_entry("found", found)?;
+            }
+            TemplateMatchError::ExpectedMatchWithin { max } => {
+                map.serialize_entry("max", &max)?;
+            }
+            TemplateMatchError::MissingParam(ref param) => {
+                map.serialize_entry("param", param)?;
+            }
+            TemplateMatchError::InvalidIgnorePattern { ref pattern, .. } => {
+                map.serialize_entry("pattern", pattern)?;
+            }
+            TemplateMatchError::InputTooLarge { limit } => {
+                map.serialize_entry("limit", &limit)?;
+            }
+            TemplateMatchError::WrongLineEnding { expected, found } => {
+                map.serialize_entry("expected", &expected.to_string())?;
+                map.serialize_entry("found", &found.to_string())?;
+            }
+            TemplateMatchError::WrongTrailingNewline { expected } => {
+                map.serialize_entry("expected", &expected)?;
+            }
+            _ => (),
+        }
+        map.end()
+    }
+}
+
+impl Serialize for At<ParseError> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        let kind = match self.desc {
+            ParseError::Lex(_) => "lex",
+            ParseError::InvalidOptions(_) => "invalid_options",
+            ParseError::ExpectedKeyFoundValue => "expected_key_found_value",
+            ParseError::EmptyItem => "empty_item",
+            ParseError::UnexpectedEndOfTokens => "unexpected_end_of_tokens",
+            ParseError::ExpectedDifferentToken { .. } => "expected_different_token",
+        };
+        map.serialize_entry("kind", kind)?;
+        map.serialize_entry("lo", &self.lo)?;
+        map.serialize_entry("hi", &self.hi)?;
+        map.serialize_entry("message", &self.desc.to_string())?;
+        if let ParseError::ExpectedDifferentToken {
+            ref expected,
+            ref found,
+        } = self.desc
+        {
+            let expected: Vec<String> = expected.iter().map(|t| t.to_string()).collect();
+            map.serialize_entry("expected", &expected)?;
+            map.serialize_entry("found", &found.to_string())?;
+        }
+        map.end()
+    }
+}
+
+impl At<TemplateMatchError> {
+    /// Returns the error as a JSON object.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("error should serialize to json")
+    }
+}
+
+impl At<ParseError> {
+    /// Returns the error as a JSON object.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("error should serialize to json")
+    }
+}
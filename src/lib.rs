@@ -28,7 +28,9 @@ body {
 Specker can check if there is a file named `output/index.html` containing
 `<body>` in some line, as well as file `output/style.css`
 containing `body {` and `}` lines. Symbol `..` matches any number of
-lines.
+lines, and `${..name}` matches the same lines while capturing them as `name`.
+Symbol `..^10` matches at most 10 lines, so the next line must be found
+within them.
 
 If there is a match error, specker can print a nice message like:
 
@@ -50,7 +52,6 @@ extern crate specker;
 use std::fs;
 use std::env;
 use std::path::PathBuf;
-use std::collections::HashMap;
 
 #[test]
 fn check_specifications() {
@@ -61,6 +62,7 @@ fn check_specifications() {
         marker: "##",
         var_start: "${",
         var_end: "}",
+        ..specker::Options::default()
     }) {
         let spec_path = maybe_spec.unwrap_or_else(|e| {
             // print nicely formatted error
@@ -78,7 +80,7 @@ fn check_specifications() {
                 let mut file = fs::File::open(&path)
                     .expect(&format!("failed to open file {:?}", &path));
 
-                if let Err(e) = item.match_contents(&mut file, &HashMap::new()) {
+                if let Err(e) = item.match_contents_default(&mut file) {
                     // print nicely formatted error
                     println!("{}", specker::display_error_for_file(&path, &e));
                     // print one-liner error
@@ -91,45 +93,246 @@ fn check_specifications() {
 
 */
 
+#[cfg(feature = "glob")]
+extern crate globset;
+#[cfg(feature = "log")]
+#[macro_use]
+extern crate log;
+#[cfg(feature = "memchr")]
+extern crate memchr;
+#[cfg(feature = "regex")]
+extern crate regex;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde")]
+extern crate serde_json;
+#[cfg(feature = "walk")]
 extern crate walkdir;
 
-mod ast;
+/// Logs matching progress at the `debug` level if the `log` feature is enabled.
+macro_rules! match_debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        debug!($($arg)*);
+    };
+}
+
+/// Logs matching progress at the `trace` level if the `log` feature is enabled.
+macro_rules! match_trace {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        trace!($($arg)*);
+    };
+}
+
+pub mod ast;
 mod display;
 mod error;
+#[cfg(feature = "glob")]
+mod glob;
+#[cfg(feature = "regex")]
+mod ignore;
+#[cfg(feature = "serde")]
+mod json;
+mod line_ending;
+mod params;
+mod report;
+#[cfg(feature = "insta")]
+pub mod snapshot;
 mod spec;
+mod spec_path;
 mod tokens;
+#[cfg(feature = "walk")]
 mod walk;
 
 pub use ast::{Match, Param};
-pub use display::{display_error, display_error_for_file, display_error_for_read};
-pub use error::At;
-pub use error::{LexError, ParseError, TemplateMatchError, TemplateWriteError};
-pub use spec::{Item, ItemIter, ItemValuesByKeyIter, Options, Spec};
+pub use display::{
+    display_diff, display_error, display_error_for_file, display_error_for_read,
+    display_line_mismatch, match_and_display, write_error, write_error_for_file,
+    write_error_for_read,
+};
+pub use error::{At, FilePosition};
+pub use error::{LexError, OptionsError, ParseError, TemplateMatchError, TemplateWriteError};
+pub use line_ending::{detect_line_ending, LineEnding};
+pub use params::{OwnedParams, ParamSource};
+pub use report::{ErrorReport, MatchSummary};
+pub use spec::{
+    CompiledItem, Item, ItemIter, ItemValuesByKeyIter, ItemValuesByPrefixIter, ItemsByParamIter,
+    LineMismatch, MatchEvent, MatchObserver, MatchOptions, Matcher, Options, OwnedOptions, Spec,
+    VarsIter, MAX_INCLUDE_DEPTH,
+};
+use std::error::Error as StdError;
 use std::{fmt, io, path, result};
-pub use walk::{walk_spec_dir, SpecPath, SpecWalkIter};
+pub use tokens::{lex, Token, TokenIter, TokenRef, TokenValue, TokenValueRef};
+pub use spec_path::{parse_specs_from_map, run_spec, run_spec_in_map, ItemOutcome, SpecPath};
+#[cfg(feature = "walk")]
+pub use walk::{
+    walk_spec_dir, walk_spec_dir_collect, walk_spec_dir_ext, walk_spec_dir_filtered, SpecWalkIter,
+};
+
+/// Byte-level parsing helpers for building custom lexers.
+///
+/// This API is not stable and may change between minor versions.
+#[cfg(feature = "unstable-combinators")]
+pub use tokens::combinator;
+
+/// Asserts that contents match a specification item, and panics with a nicely formatted
+/// error otherwise.
+///
+/// Contents can be anything that can be referenced as bytes, and params default to none.
+///
+/// ```
+/// #[macro_use]
+/// extern crate specker;
+///
+/// use specker::{Item, Match};
+/// use std::collections::HashMap;
+///
+/// # fn main() {
+/// let template = [Match::Text("Hello, ".into()), Match::Var("name".into())];
+/// let item = Item::new(&[], &template);
+///
+/// let mut params = HashMap::new();
+/// params.insert("name", "world");
+///
+/// assert_matches!(item, "Hello, world", &params);
+/// # }
+/// ```
+#[cfg(feature = "test-macros")]
+#[macro_export]
+macro_rules! assert_matches {
+    ($item:expr, $contents:expr) => {
+        assert_matches!($item, $contents, &::std::collections::HashMap::new())
+    };
+    ($item:expr, $contents:expr, $params:expr) => {{
+        let contents: &[u8] = ::std::convert::AsRef::<[u8]>::as_ref(&$contents);
+        if let Err(e) = $item.match_contents(&mut ::std::io::Cursor::new(contents), $params) {
+            panic!(
+                "\n{}",
+                $crate::display_error_for_read(
+                    ::std::path::Path::new("contents"),
+                    &mut ::std::io::Cursor::new(contents),
+                    &e
+                )
+            );
+        }
+    }};
+}
+
+/// Asserts that written bytes are equal to the expected string.
+#[cfg(feature = "test-macros")]
+#[macro_export]
+macro_rules! assert_contents {
+    ($contents:expr, $expected:expr) => {
+        assert_eq!(
+            ::std::str::from_utf8($contents).expect("contents are not valid utf8"),
+            $expected
+        )
+    };
+}
 
 /// Specification iteration or parsing error.
 #[derive(Debug)]
 pub enum Error {
+    #[cfg(feature = "walk")]
     WalkDir(walkdir::Error),
+    #[cfg(feature = "glob")]
+    Glob(globset::Error),
     Io(io::Error),
     StripPrefixError(path::StripPrefixError),
+    InvalidOptions(error::OptionsError),
+    MaxDepthExceeded {
+        path: path::PathBuf,
+        max_depth: usize,
+    },
     Parse {
         path: path::PathBuf,
         err: error::At<error::ParseError>,
     },
+    Match {
+        path: path::PathBuf,
+        err: error::At<error::TemplateMatchError>,
+    },
+    Write {
+        path: path::PathBuf,
+        err: error::TemplateWriteError,
+    },
+    IncludeCycle {
+        path: path::PathBuf,
+    },
+    Include {
+        path: path::PathBuf,
+        include: path::PathBuf,
+        span: (FilePosition, FilePosition),
+        err: io::Error,
+    },
+}
+
+impl Error {
+    /// Converts the error into a report that can be cloned.
+    ///
+    /// The report keeps the message, and the path and positions of the error if it has them.
+    /// Inner errors, such as io errors, are only kept as part of the message.
+    pub fn into_report(self) -> ErrorReport {
+        let (path, span) = match self {
+            Error::Parse { ref path, ref err } => (Some(path.clone()), Some((err.lo, err.hi))),
+            Error::Match { ref path, ref err } => (Some(path.clone()), Some((err.lo, err.hi))),
+            Error::Write { ref path, .. }
+            | Error::MaxDepthExceeded { ref path, .. }
+            | Error::IncludeCycle { ref path } => (Some(path.clone()), None),
+            Error::Include { ref path, span, .. } => (Some(path.clone()), Some(span)),
+            #[cfg(feature = "walk")]
+            Error::WalkDir(ref e) => (e.path().map(|p| p.to_path_buf()), None),
+            #[cfg(feature = "glob")]
+            Error::Glob(_) => (None, None),
+            Error::Io(_) | Error::StripPrefixError(_) | Error::InvalidOptions(_) => (None, None),
+        };
+
+        ErrorReport {
+            message: self.to_string(),
+            path: path,
+            span: span,
+        }
+    }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
+            #[cfg(feature = "walk")]
             Error::WalkDir(ref e) => e.fmt(f),
+            #[cfg(feature = "glob")]
+            Error::Glob(ref e) => e.fmt(f),
             Error::Io(ref e) => e.fmt(f),
             Error::StripPrefixError(ref e) => e.fmt(f),
+            Error::InvalidOptions(ref e) => e.fmt(f),
+            Error::MaxDepthExceeded {
+                ref path,
+                max_depth,
+            } => write!(f, "{:?} is nested deeper than {} levels", path, max_depth),
             Error::Parse {
                 ref path,
                 err: ref e,
             } => write!(f, "{} in {:?}", e, path),
+            Error::Match {
+                ref path,
+                err: ref e,
+            } => write!(f, "{} in {:?}", e, path),
+            Error::Write {
+                ref path,
+                err: ref e,
+            } => write!(f, "{} in {:?}", e, path),
+            Error::IncludeCycle { ref path } => write!(f, "{:?} includes itself", path),
+            Error::Include {
+                ref path,
+                ref include,
+                ref span,
+                ref err,
+            } => write!(
+                f,
+                "failed to include {:?}: {} at {} in {:?}",
+                include, err, span.0, path
+            ),
         }
     }
 }
@@ -137,20 +340,55 @@ impl fmt::Display for Error {
 impl ::std::error::Error for Error {
     fn description(&self) -> &str {
         match *self {
+            #[cfg(feature = "walk")]
             Error::WalkDir(ref e) => e.description(),
+            #[cfg(feature = "glob")]
+            Error::Glob(ref e) => e.description(),
             Error::Io(ref e) => e.description(),
             Error::StripPrefixError(ref e) => e.description(),
+            Error::InvalidOptions(ref e) => e.description(),
+            Error::MaxDepthExceeded { .. } => "maximum depth exceeded",
             Error::Parse { ref err, .. } => err.description(),
+            Error::Match { ref err, .. } => err.description(),
+            Error::Write { ref err, .. } => err.description(),
+            Error::IncludeCycle { .. } => "include cycle",
+            Error::Include { .. } => "failed to include specification",
+        }
+    }
+
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match *self {
+            #[cfg(feature = "walk")]
+            Error::WalkDir(ref e) => Some(e),
+            #[cfg(feature = "glob")]
+            Error::Glob(ref e) => Some(e),
+            Error::Io(ref e) => Some(e),
+            Error::StripPrefixError(ref e) => Some(e),
+            Error::InvalidOptions(ref e) => Some(e),
+            Error::MaxDepthExceeded { .. } => None,
+            Error::Parse { ref err, .. } => Some(err),
+            Error::Match { ref err, .. } => Some(err),
+            Error::Write { ref err, .. } => Some(err),
+            Error::IncludeCycle { .. } => None,
+            Error::Include { ref err, .. } => Some(err),
         }
     }
 }
 
+#[cfg(feature = "walk")]
 impl From<walkdir::Error> for Error {
     fn from(other: walkdir::Error) -> Error {
         Error::WalkDir(other)
     }
 }
 
+#[cfg(feature = "glob")]
+impl From<globset::Error> for Error {
+    fn from(other: globset::Error) -> Error {
+        Error::Glob(other)
+    }
+}
+
 impl From<io::Error> for Error {
     fn from(other: io::Error) -> Error {
         Error::Io(other)
@@ -165,6 +403,10 @@ impl From<path::StripPrefixError> for Error {
 
 impl From<(path::PathBuf, error::At<error::ParseError>)> for Error {
     fn from((path, other): (path::PathBuf, error::At<error::ParseError>)) -> Error {
+        if let error::ParseError::InvalidOptions(e) = other.desc {
+            return Error::InvalidOptions(e);
+        }
+
         Error::Parse {
             path: path,
             err: other,
@@ -172,5 +414,23 @@ impl From<(path::PathBuf, error::At<error::ParseError>)> for Error {
     }
 }
 
+impl From<(path::PathBuf, error::At<error::TemplateMatchError>)> for Error {
+    fn from((path, other): (path::PathBuf, error::At<error::TemplateMatchError>)) -> Error {
+        Error::Match {
+            path: path,
+            err: other,
+        }
+    }
+}
+
+impl From<(path::PathBuf, error::TemplateWriteError)> for Error {
+    fn from((path, other): (path::PathBuf, error::TemplateWriteError)) -> Error {
+        Error::Write {
+            path: path,
+            err: other,
+        }
+    }
+}
+
 /// Specification iteration or parsing result.
 pub type Result<T> = result::Result<T, Error>;
@@ -0,0 +1,151 @@
+// Copyright 2017 Nerijus Arlauskas
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use error::At;
+use std::fmt;
+
+/// Line ending style used in file contents.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LineEnding {
+    /// Contents have no line endings.
+    None,
+    /// All lines end with `\n`.
+    Lf,
+    /// All lines end with `\r\n`.
+    CrLf,
+    /// All lines end with `\r`.
+    Cr,
+    /// Contents contain more than one line ending style.
+    Mixed,
+}
+
+impl fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LineEnding::None => "no line endings".fmt(f),
+            LineEnding::Lf => "LF line endings".fmt(f),
+            LineEnding::CrLf => "CRLF line endings".fmt(f),
+            LineEnding::Cr => "CR line endings".fmt(f),
+            LineEnding::Mixed => "mixed line endings".fmt(f),
+        }
+    }
+}
+
+/// Detects the line ending style used in contents.
+pub fn detect_line_ending(contents: &[u8]) -> LineEnding {
+    let mut detected = LineEnding::None;
+    let mut i = 0;
+
+    while i < contents.len() {
+        let (ending, len) = match line_ending_at(contents, i) {
+            Some(found) => found,
+            None => {
+                i += 1;
+                continue;
+            }
+        };
+        i += len;
+
+        detected = match detected {
+            LineEnding::None => ending,
+            other if other == ending => other,
+            _ => return LineEnding::Mixed,
+        };
+    }
+
+    detected
+}
+
+/// Returns the byte, the style and the length of the first line ending that is not allowed
+/// by the `expected` style.
+///
+/// `Mixed` allows any line endings, and `None` allows none.
+pub fn find_wrong_line_ending(
+    contents: &[u8],
+    expected: LineEnding,
+) -> Option<(usize, LineEnding, usize)> {
+    if expected == LineEnding::Mixed {
+        return None;
+    }
+
+    let mut i = 0;
+    while i < contents.len() {
+        match line_ending_at(contents, i) {
+            Some((ending, len)) => {
+                if ending != expected {
+                    return Some((i, ending, len));
+                }
+                i += len;
+            }
+            None => i += 1,
+        }
+    }
+
+    None
+}
+
+/// Input contents with `\r` removed from every `\r\n`.
+///
+/// Keeps enough information to map positions in normalized contents back to the original input.
+pub struct CrlfNormalized {
+    contents: Vec<u8>,
+    /// Byte offsets in normalized contents of every `\n` that had its `\r` removed.
+    removed: Vec<usize>,
+}
+
+impl CrlfNormalized {
+    /// Replaces every `\r\n` with `\n`.
+    pub fn normalize(input: &[u8]) -> CrlfNormalized {
+        let mut contents = Vec::with_capacity(input.len());
+        let mut removed = Vec::new();
+
+        let mut i = 0;
+        while i < input.len() {
+            if input[i] == b'\r' && input.get(i + 1) == Some(&b'\n') {
+                removed.push(contents.len());
+                i += 1;
+                continue;
+            }
+            contents.push(input[i]);
+            i += 1;
+        }
+
+        CrlfNormalized {
+            contents: contents,
+            removed: removed,
+        }
+    }
+
+    /// Normalized contents.
+    pub fn contents(&self) -> &[u8] {
+        &self.contents
+    }
+
+    /// Maps error positions in normalized contents to positions in the original input.
+    ///
+    /// Only the bytes change, because lines and columns are the same in both.
+    pub fn remap<T: fmt::Debug>(&self, mut err: At<T>) -> At<T> {
+        err.lo.byte = self.original_byte(err.lo.byte);
+        err.hi.byte = self.original_byte(err.hi.byte);
+        err
+    }
+
+    fn original_byte(&self, byte: usize) -> usize {
+        // a position at the `\n` points to its removed `\r`
+        byte + self.removed.partition_point(|&removed| removed < byte)
+    }
+}
+
+/// Returns the style and the length of the line ending at byte `i`.
+fn line_ending_at(contents: &[u8], i: usize) -> Option<(LineEnding, usize)> {
+    match contents[i] {
+        b'\n' => Some((LineEnding::Lf, 1)),
+        b'\r' if contents.get(i + 1) == Some(&b'\n') => Some((LineEnding::CrLf, 2)),
+        b'\r' => Some((LineEnding::Cr, 1)),
+        _ => None,
+    }
+}
@@ -0,0 +1,78 @@
+// Copyright 2017 Nerijus Arlauskas
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::collections::HashMap;
+
+/// Values of template vars, used when matching or writing a template.
+pub trait ParamSource {
+    /// Returns the value of the var with the specified key.
+    fn get(&self, key: &str) -> Option<&str>;
+}
+
+impl ParamSource for HashMap<&str, &str> {
+    fn get(&self, key: &str) -> Option<&str> {
+        HashMap::get(self, key).cloned()
+    }
+}
+
+/// Params taken from a map that owns its keys and values.
+///
+/// Only `HashMap<&str, &str>` implements `ParamSource`, so that the type of an empty
+/// `&HashMap::new()` can be inferred. Wrap maps of strings with this instead.
+pub struct OwnedParams<'a> {
+    params: &'a HashMap<String, String>,
+}
+
+impl<'a> OwnedParams<'a> {
+    pub fn new(params: &'a HashMap<String, String>) -> OwnedParams<'a> {
+        OwnedParams { params: params }
+    }
+}
+
+impl<'a> ParamSource for OwnedParams<'a> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.params.get(key).map(|v| &v[..])
+    }
+}
+
+impl ParamSource for [(&str, &str)] {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.iter().find(|&&(k, _)| k == key).map(|&(_, v)| v)
+    }
+}
+
+impl<T: ParamSource + ?Sized> ParamSource for &T {
+    fn get(&self, key: &str) -> Option<&str> {
+        (**self).get(key)
+    }
+}
+
+/// Params that fall back to values taken from the environment.
+pub struct WithEnv<'a, P: 'a + ?Sized> {
+    params: &'a P,
+    env: &'a [(String, String)],
+}
+
+impl<'a, P: ParamSource + ?Sized> WithEnv<'a, P> {
+    pub fn new(params: &'a P, env: &'a [(String, String)]) -> WithEnv<'a, P> {
+        WithEnv {
+            params: params,
+            env: env,
+        }
+    }
+}
+
+impl<'a, P: ParamSource + ?Sized> ParamSource for WithEnv<'a, P> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.params.get(key).or_else(|| {
+            self.env
+                .iter()
+                .find(|&&(ref k, _)| k == key)
+                .map(|&(_, ref v)| &v[..])
+        })
+    }
+}
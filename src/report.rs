@@ -0,0 +1,121 @@
+// Copyright 2017 Nerijus Arlauskas
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use {At, FilePosition, TemplateMatchError};
+
+/// Counts of matched files and a list of failures, for reporting over many files.
+#[derive(Debug, Default)]
+pub struct MatchSummary {
+    /// Number of files that matched.
+    pub passed: usize,
+    /// Files that did not match, together with the errors.
+    pub failures: Vec<(PathBuf, At<TemplateMatchError>)>,
+}
+
+impl MatchSummary {
+    /// Creates an empty summary.
+    pub fn new() -> MatchSummary {
+        MatchSummary::default()
+    }
+
+    /// Records the result of matching a file.
+    pub fn record<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        result: Result<(), At<TemplateMatchError>>,
+    ) {
+        match result {
+            Ok(()) => self.passed += 1,
+            Err(e) => self.failures.push((path.as_ref().to_path_buf(), e)),
+        }
+    }
+
+    /// Number of files that did not match.
+    pub fn failed(&self) -> usize {
+        self.failures.len()
+    }
+
+    /// Number of recorded files.
+    pub fn total(&self) -> usize {
+        self.passed + self.failed()
+    }
+
+    /// Returns true if all recorded files matched.
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Returns the summary as a JSON object.
+    pub fn to_json(&self) -> String {
+        let mut json = format!(
+            "{{\"passed\":{},\"failed\":{},\"failures\":[",
+            self.passed,
+            self.failed()
+        );
+        for (i, (path, err)) in self.failures.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str(&format!(
+                "{{\"path\":{},\"line\":{},\"col\":{},\"message\":{}}}",
+                json_string(&path.to_string_lossy()),
+                err.lo.line,
+                err.lo.col,
+                json_string(&err.desc.to_string())
+            ));
+        }
+        json.push_str("]}");
+        json
+    }
+}
+
+impl fmt::Display for MatchSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (path, err) in &self.failures {
+            writeln!(f, "{}: {}", path.display(), err)?;
+        }
+        write!(f, "{} passed, {} failed", self.passed, self.failed())
+    }
+}
+
+/// Owned snapshot of an `Error` that can be cloned, stored and displayed again.
+///
+/// Created with `Error::into_report`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ErrorReport {
+    /// Error message, the same as the display of the error.
+    pub message: String,
+    /// Path of the file that the error is about, if any.
+    pub path: Option<PathBuf>,
+    /// Start and end positions of the error in the file, if any.
+    pub span: Option<(FilePosition, FilePosition)>,
+}
+
+impl fmt::Display for ErrorReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.message.fmt(f)
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut result = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
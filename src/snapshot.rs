@@ -0,0 +1,42 @@
+// Copyright 2017 Nerijus Arlauskas
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Helpers for using specification items together with snapshot tests.
+//!
+//! The returned strings can be passed to `insta::assert_snapshot!`.
+
+use display::template_lines;
+use std::collections::HashMap;
+use {At, Item, ParamSource, TemplateMatchError};
+
+/// Returns item contents with vars replaced by `params`.
+///
+/// Unlike `Item::write_contents`, this never fails: missing vars are kept as `${name}`, and
+/// symbols that match any lines or any text are kept as `..`.
+pub fn item_snapshot<P: ParamSource + ?Sized>(item: &Item, params: &P) -> String {
+    template_lines(item.template, params).join("\n")
+}
+
+/// Returns match error message where param values are replaced with `${name}`.
+///
+/// This keeps the message stable when param values change between runs, for example
+/// when they contain temporary paths.
+pub fn error_snapshot(err: &At<TemplateMatchError>, params: &HashMap<&str, &str>) -> String {
+    let mut params: Vec<(&str, &str)> = params
+        .iter()
+        .filter(|&(_, value)| !value.is_empty())
+        .map(|(key, value)| (*key, *value))
+        .collect();
+    // replace longer values first, so that values that contain other values are redacted whole
+    params.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(b.0)));
+
+    let mut message = err.to_string();
+    for (key, value) in params {
+        message = message.replace(value, &format!("${{{}}}", key));
+    }
+    message
+}
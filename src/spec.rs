@@ -5,15 +5,34 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
+use Error;
 use ast;
-use error::{At, FilePosition, ParseError, TemplateMatchError, TemplateWriteError};
-use std::collections::HashMap;
-use std::io::{Read, Write};
+use display::display_error_for_read;
+#[cfg(feature = "memchr")]
+use memchr;
+use error::{At, FilePosition, OptionsError, ParseError, TemplateMatchError, TemplateWriteError};
+#[cfg(feature = "regex")]
+use ignore;
+use line_ending::{self, LineEnding};
+use params::{ParamSource, WithEnv};
+use std::collections::{BTreeSet, HashMap};
+use std::env;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::iter::FusedIterator;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::result;
 use std::slice;
 use std::str;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokens;
 
+/// Empty params for methods that do not take them.
+const NO_PARAMS: &[(&str, &str)] = &[];
+
 /// Specification parser options.
 #[derive(Copy, Clone, Debug)]
 pub struct Options<'a> {
@@ -25,12 +44,282 @@ pub struct Options<'a> {
     pub var_start: &'a str,
     /// Var end suffix.
     pub var_end: &'a str,
+    /// Separator between param key and value, `:` by default.
+    ///
+    /// A value continues on the next line if that line has the marker and the separator with
+    /// no key between them, such as `##: more`. Continuation values are trimmed and quoted the
+    /// same way, and are joined to the value with `\n`. A line with an empty key only
+    /// continues a value directly after it, so `##: more` after a param without a value, or
+    /// after template content, is still a param with an empty key.
+    pub param_separator: &'a str,
+    /// Sequence that makes the following `var_start`, `marker`, `skip_lines` or escape
+    /// sequence literal text.
+    ///
+    /// For example, with `\` as the escape, `\##` at the line start is the text `##`
+    /// instead of a param line, and `\\` is a single `\`. An escape followed by anything
+    /// else is literal text too. There is no escape by default.
+    pub escape: Option<&'a str>,
+    /// Allow spaces and tabs before `marker` and `skip_lines` at the line start. Disabled by
+    /// default.
+    pub allow_indented_markers: bool,
+    /// Compare param keys ignoring ASCII case.
+    pub case_insensitive_keys: bool,
+    /// Interpret `\n`, `\t` and `\\` in template text as a new line, a tab and a backslash.
+    ///
+    /// A `\n` splits the text into two template lines, as if the content continued on the
+    /// next line. Any other backslash is literal. Escapes are interpreted after `escape`, and
+    /// lexer tokens returned by `Spec::parse_with_tokens` are not changed. Disabled by default.
+    pub interpret_escapes: bool,
+}
+
+impl Default for Options<'static> {
+    /// Returns options for specifications like `## file: a.txt`, with `..` to skip lines and
+    /// `${name}` vars, and all other settings disabled.
+    fn default() -> Options<'static> {
+        Options {
+            skip_lines: "..",
+            marker: "##",
+            var_start: "${",
+            var_end: "}",
+            param_separator: ":",
+            escape: None,
+            allow_indented_markers: false,
+            case_insensitive_keys: false,
+            interpret_escapes: false,
+        }
+    }
+}
+
+impl<'a> Options<'a> {
+    /// Checks that options can be used to tokenize a specification.
+    ///
+    /// All options must be non-empty, and `marker`, `skip_lines`, `var_start` and `escape`
+    /// can not be prefixes of each other. `param_separator` can not be a prefix of `marker` or
+    /// `var_start`, or the other way around. `var_start` and `var_end` can not be equal.
+    /// No option can contain `\n` or `\r`, because the lexer matches them within a line.
+    pub fn validate(&self) -> result::Result<(), OptionsError> {
+        let mut named = vec![
+            ("marker", self.marker),
+            ("skip_lines", self.skip_lines),
+            ("var_start", self.var_start),
+        ];
+        if let Some(escape) = self.escape {
+            named.push(("escape", escape));
+        }
+        let prefixed = named.len();
+        named.push(("var_end", self.var_end));
+        named.push(("param_separator", self.param_separator));
+
+        for &(name, value) in &named {
+            if value.is_empty() {
+                return Err(OptionsError::Empty(name));
+            }
+            if value.contains(&['\n', '\r'][..]) {
+                return Err(OptionsError::NewLine(name));
+            }
+        }
+
+        for (i, &(name_a, a)) in named[..prefixed].iter().enumerate() {
+            for &(name_b, b) in &named[i + 1..prefixed] {
+                if b.starts_with(a) {
+                    return Err(OptionsError::Overlap {
+                        prefix: name_a,
+                        other: name_b,
+                    });
+                }
+                if a.starts_with(b) {
+                    return Err(OptionsError::Overlap {
+                        prefix: name_b,
+                        other: name_a,
+                    });
+                }
+            }
+        }
+
+        for &(name, value) in &[("marker", self.marker), ("var_start", self.var_start)] {
+            if value.starts_with(self.param_separator) {
+                return Err(OptionsError::Overlap {
+                    prefix: "param_separator",
+                    other: name,
+                });
+            }
+            if self.param_separator.starts_with(value) {
+                return Err(OptionsError::Overlap {
+                    prefix: name,
+                    other: "param_separator",
+                });
+            }
+        }
+
+        if self.var_start == self.var_end {
+            return Err(OptionsError::Overlap {
+                prefix: "var_start",
+                other: "var_end",
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Copies option strings into `OwnedOptions` that can be stored without a lifetime.
+    pub fn to_owned(&self) -> OwnedOptions {
+        OwnedOptions {
+            skip_lines: self.skip_lines.into(),
+            marker: self.marker.into(),
+            var_start: self.var_start.into(),
+            var_end: self.var_end.into(),
+            param_separator: self.param_separator.into(),
+            escape: self.escape.map(|e| e.into()),
+            allow_indented_markers: self.allow_indented_markers,
+            case_insensitive_keys: self.case_insensitive_keys,
+            interpret_escapes: self.interpret_escapes,
+        }
+    }
+}
+
+/// Specification parser options that own their strings.
+///
+/// Borrow them as `Options` with `Options::from(&owned)`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OwnedOptions {
+    /// String that marks multiple lines to be skipped.
+    pub skip_lines: String,
+    /// Prefix that marks the line as containing a parameter.
+    pub marker: String,
+    /// Var start prefix.
+    pub var_start: String,
+    /// Var end suffix.
+    pub var_end: String,
+    /// Separator between param key and value, usually `:`.
+    pub param_separator: String,
+    /// Sequence that makes the following special sequence literal text.
+    pub escape: Option<String>,
+    /// Allow spaces and tabs before `marker` and `skip_lines` at the line start.
+    pub allow_indented_markers: bool,
+    /// Compare param keys ignoring ASCII case.
+    pub case_insensitive_keys: bool,
+    /// Interpret `\n`, `\t` and `\\` in template text.
+    pub interpret_escapes: bool,
+}
+
+impl<'a> From<&'a OwnedOptions> for Options<'a> {
+    fn from(other: &'a OwnedOptions) -> Options<'a> {
+        Options {
+            skip_lines: &other.skip_lines,
+            marker: &other.marker,
+            var_start: &other.var_start,
+            var_end: &other.var_end,
+            param_separator: &other.param_separator,
+            escape: other.escape.as_ref().map(|e| &e[..]),
+            allow_indented_markers: other.allow_indented_markers,
+            case_insensitive_keys: other.case_insensitive_keys,
+            interpret_escapes: other.interpret_escapes,
+        }
+    }
+}
+
+/// Options that change how item contents are matched.
+#[derive(Clone, Debug)]
+pub struct MatchOptions {
+    /// Lines that match any of these regular expressions are removed from the input before
+    /// matching. Reported error positions still point to the original input.
+    #[cfg(feature = "regex")]
+    pub ignore_line_patterns: Vec<String>,
+    /// Treat consecutive blank lines as a single separator.
+    ///
+    /// Where the template contains one or more blank lines in a row, the input must contain
+    /// at least one blank line, and any number of them is accepted. Lines that contain only
+    /// a var are not considered blank. Templates without blank lines are matched the same
+    /// way as without this option, so extra blank lines in input are still an error.
+    /// A final `NewLine` in the template is not a blank line, so the end of input
+    /// is matched as usual, except that trailing blank lines are collapsed like any others.
+    pub collapse_blank_lines: bool,
+    /// Require every `MultipleLines` symbol to match at least one line.
+    ///
+    /// When it would match no lines, matching fails with `ExpectedSkippedLine`.
+    pub skip_at_least_one_line: bool,
+    /// Require the template to match the whole input. Enabled by default.
+    ///
+    /// When disabled, the template only has to match somewhere inside the input, as if
+    /// there were `MultipleLines` symbols at its both ends. The template is still matched
+    /// line by line, so its first line must match from the start of some input line, and
+    /// its last line must end at the end of that input line. An explicit leading or trailing
+    /// `MultipleLines` changes nothing in this mode, except that with `skip_at_least_one_line`
+    /// it still has to match at least one line.
+    pub anchored: bool,
+    /// Treat runs of spaces and tabs as a single space when comparing text and var values.
+    ///
+    /// A run of whitespace in the template matches a run of any length in the input, but not
+    /// its absence. This also applies to whitespace at the start of a text, so text indentation
+    /// is not compared exactly. `Indent` symbols are not affected and still require exact
+    /// indentation.
+    pub normalize_whitespace: bool,
+    /// Fail with `WrongLineEnding` at the first line ending that is not of this style.
+    ///
+    /// `Mixed` allows any line endings, and `None` allows none. The whole input is checked,
+    /// including lines that are skipped or ignored.
+    pub require_line_ending: Option<LineEnding>,
+    /// Fail with `WrongTrailingNewline` if the input does not end as required.
+    ///
+    /// `Some(true)` requires exactly one line ending at the end of input, and `Some(false)`
+    /// requires none. Empty input always passes. `None` leaves it to the template.
+    pub require_trailing_newline: Option<bool>,
+    /// Remove the common indentation from template and input lines before comparing them.
+    ///
+    /// The common indentation is computed once for the whole item: the longest run of spaces
+    /// and tabs that starts every non-blank template line, and separately for every non-blank
+    /// input line, including skipped ones. A template line that starts with a var or an
+    /// `Indent` symbol has no indentation. Only the common part is removed, so the remaining
+    /// relative indentation is still compared exactly.
+    pub dedent: bool,
+    /// Let the end of input satisfy a final `NewLine` in the template.
+    ///
+    /// Input that ends right where the template expects its last new line matches instead of
+    /// failing with `ExpectedEol`. Only one final new line can be missing, and input with extra
+    /// lines after the template still fails with `ExpectedEof`.
+    pub ignore_final_newline: bool,
+    /// Replace every `\r\n` in the input with `\n` before matching.
+    ///
+    /// A template written with LF line endings then matches CRLF input, including multi-line
+    /// var values and captured lines. Var values are compared as they are, so they should use
+    /// LF line endings too. Reported error positions still point to the original input, and
+    /// `require_line_ending` still checks the original line endings.
+    pub normalize_crlf: bool,
+    /// Report a failing line of a block with more than one line as `BlockMismatch`.
+    ///
+    /// A block is a run of template lines between `MultipleLines` symbols. The error contains
+    /// all expected block lines with var values substituted, and as many input lines starting
+    /// from `first_line`, where the block started matching. Its positions point at the first
+    /// input line that differs, or at the end of input.
+    /// Single-line blocks and missing params are reported as usual.
+    pub report_block_mismatch: bool,
+}
+
+impl Default for MatchOptions {
+    fn default() -> MatchOptions {
+        MatchOptions {
+            #[cfg(feature = "regex")]
+            ignore_line_patterns: Vec::new(),
+            collapse_blank_lines: false,
+            skip_at_least_one_line: false,
+            anchored: true,
+            normalize_whitespace: false,
+            require_line_ending: None,
+            require_trailing_newline: None,
+            dedent: false,
+            ignore_final_newline: false,
+            normalize_crlf: false,
+            report_block_mismatch: false,
+        }
+    }
 }
 
 /// Parsed specification.
 #[derive(Debug, Clone)]
 pub struct Spec {
     ast: ast::Spec,
+    case_insensitive_keys: bool,
+    source: Option<Arc<[u8]>>,
 }
 
 impl<'a> IntoIterator for &'a Spec {
@@ -40,6 +329,7 @@ impl<'a> IntoIterator for &'a Spec {
     fn into_iter(self) -> Self::IntoIter {
         ItemIter {
             inner: self.ast.items.iter(),
+            case_insensitive_keys: self.case_insensitive_keys,
         }
     }
 }
@@ -50,12 +340,230 @@ impl Spec {
         options: Options<'a>,
         contents: &'a [u8],
     ) -> result::Result<Spec, At<ParseError>> {
+        Spec::parse_with_strictness(options, contents, false)
+    }
+
+    /// Parse specification from in-memory contents and keep a copy of them.
+    ///
+    /// The kept source is used by `Spec::display_error` to show the lines of an error.
+    pub fn parse_with_source<'a>(
+        options: Options<'a>,
+        contents: &'a [u8],
+    ) -> result::Result<Spec, At<ParseError>> {
+        let mut spec = Spec::parse(options, contents)?;
+        spec.source = Some(Arc::from(contents));
+        Ok(spec)
+    }
+
+    /// Returns the source of this specification if it was parsed with `parse_with_source`.
+    pub fn source(&self) -> Option<&[u8]> {
+        self.source.as_ref().map(|s| &s[..])
+    }
+
+    /// Display nice error that combines line and column info with the specification source.
+    ///
+    /// Without the source, only the error message and position are displayed.
+    pub fn display_error<T>(&self, e: &At<T>) -> String
+    where
+        T: fmt::Display + fmt::Debug + 'static,
+    {
+        match self.source {
+            Some(ref source) => display_error_for_read(Path::new("<spec>"), &mut &source[..], e),
+            None => e.to_string(),
+        }
+    }
+
+    /// Parse specification from in-memory contents, failing with `ParseError::EmptyItem` at
+    /// every marker that has no key or value after it.
+    ///
+    /// Such markers are usually typos, and `parse` reads them as params with an empty key.
+    pub fn parse_strict<'a>(
+        options: Options<'a>,
+        contents: &'a [u8],
+    ) -> result::Result<Spec, At<ParseError>> {
+        Spec::parse_with_strictness(options, contents, true)
+    }
+
+    fn parse_with_strictness<'a>(
+        options: Options<'a>,
+        contents: &'a [u8],
+        strict: bool,
+    ) -> result::Result<Spec, At<ParseError>> {
+        validate_options(&options)?;
+
+        let mut parser = ast::Parser::new(tokens::tokenize(options.into(), contents).peekable());
+        if strict {
+            parser = parser.strict();
+        }
+        let mut ast = parser.parse_spec()?;
+        if options.interpret_escapes {
+            interpret_escapes(&mut ast);
+        }
+
+        Ok(Spec {
+            ast: ast,
+            case_insensitive_keys: options.case_insensitive_keys,
+            source: None,
+        })
+    }
+
+    /// Parse specification from in-memory contents and return it together with all lexer tokens.
+    pub fn parse_with_tokens<'a>(
+        options: Options<'a>,
+        contents: &'a [u8],
+    ) -> result::Result<(Spec, Vec<tokens::TokenRef<'a>>), At<ParseError>> {
+        validate_options(&options)?;
+
+        let tokens = tokens::tokenize(options.into(), contents).collect::<Vec<_>>();
+        let mut ast = ast::Parser::new(tokens.iter().cloned().peekable()).parse_spec()?;
+        if options.interpret_escapes {
+            interpret_escapes(&mut ast);
+        }
+        let spec = Spec {
+            ast: ast,
+            case_insensitive_keys: options.case_insensitive_keys,
+            source: None,
+        };
+
+        // the parser has consumed all tokens, so any lex error would have been returned above
+        Ok((spec, tokens.into_iter().collect::<result::Result<_, _>>()?))
+    }
+
+    /// Parse specification from in-memory contents, replacing every `include` param with the
+    /// items of the included specification.
+    ///
+    /// Included paths are resolved relative to the directory of `path`, the path of `contents`,
+    /// and read with `resolver`. Included items are inserted before the item that includes
+    /// them, which is removed if it has nothing else. Their positions point to the included
+    /// file, and their `origin` in the AST is its path. A file that includes itself, directly
+    /// or through other files, fails with `Error::IncludeCycle`. Includes nested deeper than
+    /// `MAX_INCLUDE_DEPTH` fail with `Error::MaxDepthExceeded`, and files that `resolver` fails
+    /// to read with `Error::Include`, which points at the including item.
+    pub fn parse_with_resolver<'a, F>(
+        options: Options<'a>,
+        contents: &'a [u8],
+        path: &Path,
+        mut resolver: F,
+    ) -> result::Result<Spec, Error>
+    where
+        F: FnMut(&Path) -> io::Result<Vec<u8>>,
+    {
+        let mut stack = Vec::new();
+        let items = parse_included(options, contents, path, &mut resolver, &mut stack)?;
+
         Ok(Spec {
-            ast: ast::Parser::new(tokens::tokenize(options.into(), contents).peekable())
-                .parse_spec()?,
+            ast: ast::Spec { items: items },
+            case_insensitive_keys: options.case_insensitive_keys,
+            source: None,
         })
     }
 
+    /// Parse several specifications from in-memory contents, separated by lines that contain
+    /// only the `separator`.
+    ///
+    /// Error positions point to the line in the whole `contents`.
+    pub fn parse_many<'a>(
+        options: Options<'a>,
+        contents: &'a [u8],
+        separator: &str,
+    ) -> result::Result<Vec<Spec>, At<ParseError>> {
+        validate_options(&options)?;
+
+        let mut specs = Vec::new();
+        for (start, line, chunk) in split_chunks(contents, separator.as_bytes()) {
+            specs.push(Spec::parse(options, chunk).map_err(|mut e| {
+                for pos in &mut [&mut e.lo, &mut e.hi] {
+                    pos.byte += start;
+                    pos.line += line;
+                }
+                e
+            })?);
+        }
+
+        Ok(specs)
+    }
+
+    /// Creates specification from the AST.
+    ///
+    /// Param keys of the created specification are case sensitive.
+    pub fn from_ast(ast: ast::Spec) -> Spec {
+        Spec {
+            ast: ast,
+            case_insensitive_keys: false,
+            source: None,
+        }
+    }
+
+    /// Returns the AST of this specification, for transformations that are not
+    /// possible with `Item` views.
+    pub fn into_ast(self) -> ast::Spec {
+        self.ast
+    }
+
+    /// Writes every item to each file listed in its `file` params.
+    ///
+    /// File paths are resolved relative to `base_dir`, and missing directories are created.
+    /// Stops at the first item that can not be written.
+    pub fn write_all<P: ParamSource + ?Sized>(
+        &self,
+        base_dir: &Path,
+        params: &P,
+    ) -> result::Result<(), TemplateWriteError> {
+        for item in self.iter() {
+            for file in item.get_params("file") {
+                item.write_to_path(&base_dir.join(file), params)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns keys of template vars used in all items.
+    pub fn vars(&self) -> BTreeSet<&str> {
+        self.iter().flat_map(|item| item.vars()).collect()
+    }
+
+    /// Returns keys of template vars in all items that are not in `params`, without duplicates.
+    pub fn missing_vars<P: ParamSource + ?Sized>(&self, params: &P) -> Vec<&str> {
+        let mut missing = Vec::new();
+        for item in self.iter() {
+            for key in item.missing_vars(params) {
+                if !missing.contains(&key) {
+                    missing.push(key);
+                }
+            }
+        }
+        missing
+    }
+
+    /// Appends items of the `other` specification to this one.
+    ///
+    /// Param keys keep the case sensitivity of this specification. The kept source is dropped
+    /// if any items are appended, because their positions point to a different source.
+    pub fn merge(&mut self, other: Spec) {
+        if !other.ast.items.is_empty() {
+            self.source = None;
+        }
+        self.ast.items.extend(other.ast.items);
+    }
+
+    /// Creates specification that contains items of all `specs` in order.
+    ///
+    /// Param keys keep the case sensitivity of the first specification.
+    pub fn from_specs<I>(specs: I) -> Spec
+    where
+        I: IntoIterator<Item = Spec>,
+    {
+        let mut specs = specs.into_iter();
+        let mut merged = match specs.next() {
+            Some(spec) => spec,
+            None => Spec::from_ast(ast::Spec { items: Vec::new() }),
+        };
+        for spec in specs {
+            merged.merge(spec);
+        }
+        merged
+    }
+
     /// Returns an iterator over the specification items.
     pub fn iter<'r>(&'r self) -> ItemIter<'r> {
         self.into_iter()
@@ -68,22 +576,84 @@ impl Spec {
             key: key,
         }
     }
+
+    /// Filter items by a param key and a value prefix and return pairs of (&item, &value).
+    ///
+    /// Only the first value of the key is compared, the same as `Item::get_param`.
+    pub fn iter_items_with_param_prefix<'r, 'p>(
+        &'r self,
+        key: &'p str,
+        prefix: &'p str,
+    ) -> ItemValuesByPrefixIter<'r, 'p> {
+        ItemValuesByPrefixIter {
+            inner: self.iter_item_values(key),
+            prefix: prefix,
+        }
+    }
+
+    /// Returns the first item with the param `key` equal to `value`.
+    ///
+    /// Only the first value of the key is compared, the same as `Item::get_param`.
+    pub fn find<'r>(&'r self, key: &str, value: &str) -> Option<Item<'r>> {
+        self.find_all(key, value).next()
+    }
+
+    /// Returns all items with the param `key` equal to `value`.
+    ///
+    /// Only the first value of the key is compared, the same as `Item::get_param`.
+    pub fn find_all<'r, 'p>(&'r self, key: &'p str, value: &'p str) -> ItemsByParamIter<'r, 'p> {
+        ItemsByParamIter {
+            inner: self.iter(),
+            key: key,
+            value: value,
+        }
+    }
+}
+
+fn validate_options(options: &Options) -> result::Result<(), At<ParseError>> {
+    options
+        .validate()
+        .map_err(|e| ParseError::InvalidOptions(e).at(FilePosition::new(), FilePosition::new()))
 }
 
 /// Specification item, that describes how a file should be matched against.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Item<'s> {
     /// Specification item params, used to differentiate between items.
     pub params: &'s [ast::Param],
     /// Parsed specification AST.
     pub template: &'s [ast::Match],
+    /// Compare param keys ignoring ASCII case.
+    pub case_insensitive_keys: bool,
+    span: (FilePosition, FilePosition),
 }
 
 impl<'s> Item<'s> {
+    /// Creates an item that was not parsed from a specification file.
+    ///
+    /// Param keys are case sensitive, and the item starts and ends at the start of file.
+    pub fn new(params: &'s [ast::Param], template: &'s [ast::Match]) -> Item<'s> {
+        Item {
+            params: params,
+            template: template,
+            case_insensitive_keys: false,
+            span: (FilePosition::new(), FilePosition::new()),
+        }
+    }
+
+    /// Returns the positions of the start of the first item param or template line and the
+    /// end of the last one in the specification file.
+    ///
+    /// Items that were not parsed from a file, such as the ones created with `Spec::from_ast`,
+    /// start and end at the start of file.
+    pub fn source_span(&self) -> (FilePosition, FilePosition) {
+        self.span
+    }
+
     /// Finds a first param in params list that has specified key and contains a value.
     pub fn get_param(&self, key: &str) -> Option<&'s str> {
         for p in self.params.iter() {
-            if p.key == key {
+            if self.key_eq(&p.key, key) {
                 match p.value {
                     Some(ref v) => return Some(&v[..]),
                     None => continue,
@@ -93,35 +663,196 @@ impl<'s> Item<'s> {
         None
     }
 
+    /// Returns values of all params in params list that have specified key and contain a value.
+    pub fn get_params(&self, key: &str) -> Vec<&'s str> {
+        self.params
+            .iter()
+            .filter(|p| self.key_eq(&p.key, key))
+            .filter_map(|p| p.value.as_ref().map(|v| &v[..]))
+            .collect()
+    }
+
+    /// Returns keys of all template vars, in template order.
+    ///
+    /// A var that is used more than once is returned every time.
+    pub fn vars(&self) -> VarsIter<'s> {
+        VarsIter {
+            inner: self.template.iter(),
+        }
+    }
+
+    /// Returns keys of template vars that are not in `params`, without duplicates.
+    pub fn missing_vars<P: ParamSource + ?Sized>(&self, params: &P) -> Vec<&'s str> {
+        let mut missing = Vec::new();
+        for key in self.vars() {
+            if params.get(key).is_none() && !missing.contains(&key) {
+                missing.push(key);
+            }
+        }
+        missing
+    }
+
+    /// Returns keys of params that occur more than once, with the source spans of every
+    /// occurrence.
+    ///
+    /// Keys are returned in the order of their first occurrence, and are compared the same
+    /// way as in `get_param`.
+    pub fn duplicate_params(&self) -> Vec<(&'s str, Vec<(FilePosition, FilePosition)>)> {
+        let mut found: Vec<(&'s str, Vec<(FilePosition, FilePosition)>)> = Vec::new();
+        for p in self.params.iter() {
+            match found.iter().position(|&(key, _)| self.key_eq(key, &p.key)) {
+                Some(i) => found[i].1.push(p.span),
+                None => found.push((&p.key, vec![p.span])),
+            }
+        }
+        found.retain(|&(_, ref spans)| spans.len() > 1);
+        found
+    }
+
+    fn key_eq(&self, a: &str, b: &str) -> bool {
+        if self.case_insensitive_keys {
+            a.eq_ignore_ascii_case(b)
+        } else {
+            a == b
+        }
+    }
+
+    /// Write specification that has no template vars to output.
+    pub fn write_contents_default<O: Write>(
+        &'s self,
+        output: &mut O,
+    ) -> result::Result<(), TemplateWriteError> {
+        self.write_contents(output, NO_PARAMS)
+    }
+
+    /// Write specification to output, taking vars that are missing from `params` from
+    /// environment variables of the same name.
+    ///
+    /// Values in `params` take precedence over the environment.
+    pub fn write_contents_env<O: Write, P: ParamSource + ?Sized>(
+        &'s self,
+        output: &mut O,
+        params: &P,
+    ) -> result::Result<(), TemplateWriteError> {
+        let env_params = self.env_params(params);
+        self.write_contents(output, &WithEnv::new(params, &env_params))
+    }
+
+    /// Try to match specification to input, taking vars that are missing from `params` from
+    /// environment variables of the same name.
+    ///
+    /// Values in `params` take precedence over the environment.
+    pub fn match_contents_env<I: Read, P: ParamSource + ?Sized>(
+        &'s self,
+        input: &mut I,
+        params: &P,
+    ) -> result::Result<(), At<TemplateMatchError>> {
+        let env_params = self.env_params(params);
+        self.match_contents(input, &WithEnv::new(params, &env_params))
+    }
+
+    /// Returns environment variable values of template vars that are missing from `params`.
+    fn env_params<P: ParamSource + ?Sized>(&self, params: &P) -> Vec<(String, String)> {
+        self.template
+            .iter()
+            .filter_map(|m| match *m {
+                ast::Match::Var(ref key) if params.get(key).is_none() => {
+                    env::var(key).ok().map(|value| (key.clone(), value))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Writes template contents to a file at `path`, creating its parent directories.
+    ///
+    /// The file is not created if the template can not be written.
+    pub fn write_to_path<P: ParamSource + ?Sized>(
+        &'s self,
+        path: &Path,
+        params: &P,
+    ) -> result::Result<(), TemplateWriteError> {
+        let mut contents = Vec::new();
+        self.write_contents(&mut contents, params)?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        File::create(path)?.write_all(&contents)?;
+        Ok(())
+    }
+
     /// Writes template contents to specified path.
-    pub fn write_contents<O: Write>(
+    pub fn write_contents<O: Write, P: ParamSource + ?Sized>(
+        &'s self,
+        output: &mut O,
+        params: &P,
+    ) -> result::Result<(), TemplateWriteError> {
+        self.write_contents_with(output, params, None)
+    }
+
+    /// Writes template contents to specified path, writing `multiline_placeholder` on its own
+    /// line in place of every skipped lines symbol.
+    ///
+    /// Without a placeholder, skipped lines can not be written, like in `write_contents`.
+    pub fn write_contents_with<O: Write, P: ParamSource + ?Sized>(
         &'s self,
         output: &mut O,
-        params: &HashMap<&str, &str>,
+        params: &P,
+        multiline_placeholder: Option<&str>,
     ) -> result::Result<(), TemplateWriteError> {
         // validation
 
         for s in self.template {
             match *s {
-                ast::Match::MultipleLines => {
+                ast::Match::MultipleLines
+                | ast::Match::LineRange { .. }
+                | ast::Match::CaptureLines(_)
+                    if multiline_placeholder.is_some() =>
+                {
+                    continue
+                }
+                ast::Match::MultipleLines
+                | ast::Match::LineRange { .. }
+                | ast::Match::CaptureLines(_)
+                | ast::Match::Gap(_) => {
                     return Err(TemplateWriteError::CanNotWriteMatchAnySymbols)
                 }
-                ast::Match::Var(ref key) if !params.contains_key(&key[..]) => {
+                ast::Match::Var(ref key) if params.get(key).is_none() => {
                     return Err(TemplateWriteError::MissingParam(key.to_owned()))
                 }
                 _ => continue,
             }
         }
 
-        for s in self.template {
+        let mut line_start = true;
+        for (i, s) in self.template.iter().enumerate() {
             match *s {
                 ast::Match::NewLine => {
                     output.write(b"\n")?;
                 }
                 ast::Match::Text(ref v) => write!(output, "{}", v)?,
-                ast::Match::Var(ref v) => write!(output, "{}", params.get(&v[..]).unwrap())?, // validated above
+                // validated above
+                ast::Match::Var(ref v) => write!(output, "{}", params.get(v).unwrap())?,
+                ast::Match::Indent(len) => write!(output, "{:1$}", "", len)?,
+                ast::Match::MultipleLines
+                | ast::Match::LineRange { .. }
+                | ast::Match::CaptureLines(_) => {
+                    // skip lines take the new lines around them
+                    if !line_start {
+                        output.write_all(b"\n")?;
+                    }
+                    // validated above
+                    write!(output, "{}", multiline_placeholder.unwrap())?;
+                    if i + 1 < self.template.len() {
+                        output.write_all(b"\n")?;
+                        line_start = true;
+                        continue;
+                    }
+                }
                 _ => unreachable!(),
             }
+            line_start = *s == ast::Match::NewLine;
         }
 
         Ok(())
@@ -134,7 +865,7 @@ impl<'s> Item<'s> {
     }
 
     /// Separates tokens into groups where each groups is a line.
-    fn get_multiline_match_groups(&'s self) -> Vec<MultilineMatchState<'s>> {
+    fn get_multiline_match_groups(&self) -> Vec<MultilineMatchState<'s>> {
         // this could be written to return an iterator, but I leave this work to someone from future
         // good luck!
 
@@ -143,12 +874,14 @@ impl<'s> Item<'s> {
 
         for state in self.template {
             match *state {
-                ast::Match::MultipleLines => {
+                ast::Match::MultipleLines
+                | ast::Match::LineRange { .. }
+                | ast::Match::CaptureLines(_) => {
                     if let Some(group) = prev_group {
                         results.push(MultilineMatchState::Line(LineGroup::new(group)));
                     }
                     prev_group = None;
-                    results.push(MultilineMatchState::MultipleLines);
+                    results.push(MultilineMatchState::MultipleLines(state));
                 }
                 ast::Match::NewLine => {
                     if let Some(group) = prev_group {
@@ -175,98 +908,1175 @@ impl<'s> Item<'s> {
         results
     }
 
+    /// Precompute line groups, so that the item can be matched against many inputs.
+    pub fn compile(&self) -> CompiledItem<'s> {
+        CompiledItem {
+            groups: self.get_multiline_match_groups(),
+        }
+    }
+
     /// Try to match specification to input and return any errors if they don't match.
     ///
     /// The values from `params` map will be substituted in as template vars.
-    pub fn match_contents<I: Read>(
+    ///
+    /// Empty input, or input that has only a byte order mark, is a single empty line. It is
+    /// matched by an empty template and by templates that have no `NewLine` and no text other
+    /// than empty text or vars with empty values. A `NewLine` fails with `ExpectedEol`, unless
+    /// `MatchOptions::ignore_final_newline` is set.
+    pub fn match_contents<I: Read, P: ParamSource + ?Sized>(
         &'s self,
         input: &mut I,
-        params: &HashMap<&str, &str>,
+        params: &P,
     ) -> result::Result<(), At<TemplateMatchError>> {
-        let mut pos = FilePosition::new();
-        let mut eol_pos = FilePosition::new();
-        let mut contents = Vec::new();
-        input
-            .read_to_end(&mut contents)
-            .map_err(|e| TemplateMatchError::from(e).at(pos, pos))?;
+        self.compile().match_contents(input, params)
+    }
 
-        let mut skip_lines_state = false;
-        let mut had_new_line = true;
-        update_eol(&pos, &mut eol_pos, &contents);
+    /// Try to match specification that has no template vars to input.
+    pub fn match_contents_default<I: Read>(
+        &'s self,
+        input: &mut I,
+    ) -> result::Result<(), At<TemplateMatchError>> {
+        self.match_contents(input, NO_PARAMS)
+    }
 
-        // sort tokens into groups that ends with new line, multiple lines, or eof
-        let line_groups = self.get_multiline_match_groups();
+    /// Returns true if input matches specification, discarding the match error.
+    pub fn match_bool<I: Read, P: ParamSource + ?Sized>(
+        &'s self,
+        input: &mut I,
+        params: &P,
+    ) -> bool {
+        self.match_contents(input, params).is_ok()
+    }
 
-        for state in line_groups {
-            match state {
-                MultilineMatchState::MultipleLines => {
-                    skip_lines_state = true;
-                }
-                MultilineMatchState::Line(line) => 'text: loop {
-                    let pos_byte = pos.byte;
-                    match line.matches(pos, &contents, params) {
-                        Ok((bytes, end_bytes)) => {
-                            if bytes == 0 && !had_new_line {
-                                return Err(TemplateMatchError::ExpectedEol.at(pos, pos));
-                            }
+    /// Returns true if the string matches specification, discarding the match error.
+    pub fn matches_str<P: ParamSource + ?Sized>(&'s self, s: &str, params: &P) -> bool {
+        self.match_bytes(s.as_bytes(), params).is_ok()
+    }
 
-                            pos.advance(bytes);
-                            pos.next_line(end_bytes);
-                            had_new_line = end_bytes > 0;
-                            skip_lines_state = false;
-                            update_eol(&pos, &mut eol_pos, &contents);
+    /// Reads the whole input and detects its line ending style.
+    ///
+    /// Use `MatchOptions::require_line_ending` to fail the match on unexpected line endings.
+    pub fn detected_line_ending<I: Read>(&self, input: &mut I) -> io::Result<LineEnding> {
+        let mut contents = Vec::new();
+        input.read_to_end(&mut contents)?;
+        Ok(line_ending::detect_line_ending(&contents))
+    }
 
-                            break 'text;
-                        }
-                        Err(err_match) => if skip_lines_state {
-                            if pos_byte >= contents.len() {
-                                match err_match {
-                                    LineGroupMatchErr::Text { pos: err_pos, text } => {
-                                        return Err(TemplateMatchError::ExpectedTextFoundEof(
-                                            text.to_string(),
-                                        ).at(err_pos, eol_pos))
-                                    }
-                                    _ => (),
-                                };
+    /// Try to match specification to input using additional match options.
+    ///
+    /// The values from `params` map will be substituted in as template vars.
+    pub fn match_contents_with<I: Read, P: ParamSource + ?Sized>(
+        &'s self,
+        input: &mut I,
+        params: &P,
+        options: &MatchOptions,
+    ) -> result::Result<(), At<TemplateMatchError>> {
+        self.compile().match_contents_with(input, params, options)
+    }
+
+    /// Try to match specification to contents that are already in memory.
+    ///
+    /// The values from `params` map will be substituted in as template vars.
+    pub fn match_bytes<P: ParamSource + ?Sized>(
+        &'s self,
+        contents: &[u8],
+        params: &P,
+    ) -> result::Result<(), At<TemplateMatchError>> {
+        self.compile().match_bytes(contents, params)
+    }
+
+    /// Try to match specification to input, but fail without reading the rest of input
+    /// if it is larger than `max_bytes`.
+    ///
+    /// The values from `params` map will be substituted in as template vars.
+    pub fn match_contents_limited<I: Read, P: ParamSource + ?Sized>(
+        &'s self,
+        input: &mut I,
+        params: &P,
+        max_bytes: usize,
+    ) -> result::Result<(), At<TemplateMatchError>> {
+        self.compile().match_contents_limited(input, params, max_bytes)
+    }
+
+    /// Try to match specification to input and report the progress to `observer`.
+    ///
+    /// The observer sees every matched line with the time spent matching it, every skipped
+    /// line, and the error. A closure that takes `&MatchEvent` can be used as an observer of
+    /// matched lines only.
+    pub fn match_contents_observed<I: Read, P: ParamSource + ?Sized>(
+        &'s self,
+        input: &mut I,
+        params: &P,
+        observer: &mut dyn MatchObserver,
+    ) -> result::Result<(), At<TemplateMatchError>> {
+        self.compile().match_contents_observed(input, params, observer)
+    }
+
+    /// Try to match specification to input and return the input byte range of every
+    /// matched template token.
+    ///
+    /// `MultipleLines` tokens report the range of skipped lines. New lines are not reported.
+    pub fn match_spans<I: Read, P: ParamSource + ?Sized>(
+        &'s self,
+        input: &mut I,
+        params: &P,
+    ) -> result::Result<Vec<(ast::Match, Range<usize>)>, At<TemplateMatchError>> {
+        self.compile().match_spans(input, params)
+    }
+
+    /// Try to match specification to input and return the number of input lines skipped by
+    /// every `MultipleLines` and `CaptureLines` token, in template order.
+    ///
+    /// For example, it can check that a header skipped by `..` is exactly 12 lines long.
+    pub fn match_skipped_lines<I: Read, P: ParamSource + ?Sized>(
+        &'s self,
+        input: &mut I,
+        params: &P,
+    ) -> result::Result<Vec<usize>, At<TemplateMatchError>> {
+        self.compile().match_skipped_lines(input, params)
+    }
+
+    /// Try to match specification to input and return the lines captured by every
+    /// `CaptureLines` token, by the captured var name.
+    ///
+    /// Captured text includes the line endings of captured lines.
+    pub fn match_captures<I: Read, P: ParamSource + ?Sized>(
+        &'s self,
+        input: &mut I,
+        params: &P,
+    ) -> result::Result<HashMap<String, String>, At<TemplateMatchError>> {
+        self.compile().match_captures(input, params)
+    }
+
+    /// Try to match specification to input and return all errors instead of the first one.
+    ///
+    /// After a line mismatch, matching continues from the next input line with the next
+    /// template line.
+    pub fn match_contents_all<I: Read, P: ParamSource + ?Sized>(
+        &'s self,
+        input: &mut I,
+        params: &P,
+    ) -> Vec<At<TemplateMatchError>> {
+        self.compile().match_contents_all(input, params)
+    }
+
+    /// Try to match specification to input and return only errors that are not in `baseline`.
+    ///
+    /// Errors are compared by kind, position and text.
+    pub fn match_against_baseline<I: Read, P: ParamSource + ?Sized>(
+        &'s self,
+        input: &mut I,
+        params: &P,
+        baseline: &[At<TemplateMatchError>],
+    ) -> Vec<At<TemplateMatchError>> {
+        self.compile().match_against_baseline(input, params, baseline)
+    }
+}
+
+/// Specification item with precomputed line groups.
+///
+/// Created with `Item::compile` and reused to match many inputs against the same item.
+#[derive(Debug)]
+pub struct CompiledItem<'s> {
+    groups: Vec<MultilineMatchState<'s>>,
+}
+
+impl<'s> CompiledItem<'s> {
+    /// Try to match specification to input and return any errors if they don't match.
+    ///
+    /// The values from `params` map will be substituted in as template vars.
+    pub fn match_contents<I: Read, P: ParamSource + ?Sized>(
+        &self,
+        input: &mut I,
+        params: &P,
+    ) -> result::Result<(), At<TemplateMatchError>> {
+        self.match_contents_with(input, params, &MatchOptions::default())
+    }
+
+    /// Try to match specification to input using additional match options.
+    ///
+    /// The values from `params` map will be substituted in as template vars.
+    pub fn match_contents_with<I: Read, P: ParamSource + ?Sized>(
+        &self,
+        input: &mut I,
+        params: &P,
+        options: &MatchOptions,
+    ) -> result::Result<(), At<TemplateMatchError>> {
+        let pos = FilePosition::new();
+        let mut contents = Vec::new();
+        let read = match self.max_prefix_lines(params, options) {
+            Some(lines) => {
+                let mut input = BufReader::new(input);
+                // one more byte to know if the trailing skip has any lines to match
+                read_lines(&mut input, lines, &mut contents)
+                    .and_then(|_| input.take(1).read_to_end(&mut contents).map(|_| ()))
+            }
+            None => input.read_to_end(&mut contents).map(|_| ()),
+        };
+        read.map_err(|e| TemplateMatchError::from(e).at(pos, pos))?;
+
+        self.match_bytes_with(&contents, params, options)
+    }
+
+    /// Returns the number of input lines the template can match if it ends with the only
+    /// `MultipleLines` symbol, so the rest of input does not need to be read.
+    fn max_prefix_lines<P: ParamSource + ?Sized>(
+        &self,
+        params: &P,
+        options: &MatchOptions,
+    ) -> Option<usize> {
+        if !options.anchored || options.collapse_blank_lines || options.dedent
+            || options.require_line_ending.is_some()
+            || options.require_trailing_newline.is_some()
+        {
+            return None;
+        }
+        #[cfg(feature = "regex")]
+        {
+            if !options.ignore_line_patterns.is_empty() {
+                return None;
+            }
+        }
+
+        let lines = match self.groups.split_last() {
+            // a trailing `LineRange` has to check that input ends within its lines
+            Some((&MultilineMatchState::MultipleLines(&ast::Match::LineRange { .. }), _)) => {
+                return None
+            }
+            Some((&MultilineMatchState::MultipleLines(_), lines)) => lines,
+            _ => return None,
+        };
+
+        let mut count = 0;
+        for group in lines {
+            match *group {
+                MultilineMatchState::MultipleLines(_) => return None,
+                MultilineMatchState::Line(ref line) => {
+                    count += 1;
+                    // var values can contain new lines too
+                    for token in &line.tokens {
+                        if let ast::Match::Var(ref key) = **token {
+                            let value = params.get(key).unwrap_or("");
+                            count += value.matches(&['\n', '\r'][..]).count();
+                        }
+                    }
+                }
+            }
+        }
+        Some(count)
+    }
+
+    /// Try to match specification to contents that are already in memory.
+    ///
+    /// The values from `params` map will be substituted in as template vars.
+    pub fn match_bytes<P: ParamSource + ?Sized>(
+        &self,
+        contents: &[u8],
+        params: &P,
+    ) -> result::Result<(), At<TemplateMatchError>> {
+        self.match_bytes_with(contents, params, &MatchOptions::default())
+    }
+
+    /// Try to match specification to contents that are already in memory using additional
+    /// match options.
+    ///
+    /// The values from `params` map will be substituted in as template vars.
+    pub fn match_bytes_with<P: ParamSource + ?Sized>(
+        &self,
+        contents: &[u8],
+        params: &P,
+        options: &MatchOptions,
+    ) -> result::Result<(), At<TemplateMatchError>> {
+        if let Some(expected) = options.require_line_ending {
+            check_line_ending(contents, expected)?;
+        }
+        if let Some(expected) = options.require_trailing_newline {
+            check_trailing_newline(contents, expected)?;
+        }
+
+        if options.normalize_crlf {
+            let normalized = line_ending::CrlfNormalized::normalize(contents);
+            return self
+                .match_without_ignored(normalized.contents(), params, options)
+                .map_err(|e| normalized.remap(e));
+        }
+
+        self.match_without_ignored(contents, params, options)
+    }
+
+    fn match_without_ignored<P: ParamSource + ?Sized>(
+        &self,
+        contents: &[u8],
+        params: &P,
+        options: &MatchOptions,
+    ) -> result::Result<(), At<TemplateMatchError>> {
+        #[cfg(feature = "regex")]
+        {
+            if !options.ignore_line_patterns.is_empty() {
+                let pos = FilePosition::new();
+                let filtered =
+                    ignore::IgnoredLines::filter(contents, &options.ignore_line_patterns)
+                        .map_err(|e| e.at(pos, pos))?;
+                return self
+                    .match_first_error(filtered.contents(), params, options)
+                    .map_err(|e| filtered.remap(e));
+            }
+        }
+
+        self.match_first_error(contents, params, options)
+    }
+
+    /// Try to match specification to input, but fail without reading the rest of input
+    /// if it is larger than `max_bytes`.
+    ///
+    /// The values from `params` map will be substituted in as template vars.
+    pub fn match_contents_limited<I: Read, P: ParamSource + ?Sized>(
+        &self,
+        input: &mut I,
+        params: &P,
+        max_bytes: usize,
+    ) -> result::Result<(), At<TemplateMatchError>> {
+        let pos = FilePosition::new();
+        let mut contents = Vec::new();
+        input
+            .take(max_bytes as u64 + 1)
+            .read_to_end(&mut contents)
+            .map_err(|e| TemplateMatchError::from(e).at(pos, pos))?;
+        if contents.len() > max_bytes {
+            return Err(TemplateMatchError::InputTooLarge { limit: max_bytes }.at(pos, pos));
+        }
+
+        self.match_bytes(&contents, params)
+    }
+
+    fn match_first_error<P: ParamSource + ?Sized>(
+        &self,
+        contents: &[u8],
+        params: &P,
+        options: &MatchOptions,
+    ) -> result::Result<(), At<TemplateMatchError>> {
+        match self.match_bytes_all(contents, params, options, true, None)
+            .into_iter()
+            .next()
+        {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Try to match specification to input and report the progress to `observer`.
+    ///
+    /// The observer sees every matched line with the time spent matching it, every skipped
+    /// line, and the error. A closure that takes `&MatchEvent` can be used as an observer of
+    /// matched lines only.
+    pub fn match_contents_observed<I: Read, P: ParamSource + ?Sized>(
+        &self,
+        input: &mut I,
+        params: &P,
+        observer: &mut dyn MatchObserver,
+    ) -> result::Result<(), At<TemplateMatchError>> {
+        let pos = FilePosition::new();
+        let mut contents = Vec::new();
+        let result = match input.read_to_end(&mut contents) {
+            Ok(_) => {
+                let options = MatchOptions::default();
+                match self.match_bytes_all(&contents, params, &options, true, Some(observer))
+                    .into_iter()
+                    .next()
+                {
+                    Some(e) => Err(e),
+                    None => Ok(()),
+                }
+            }
+            Err(e) => Err(TemplateMatchError::from(e).at(pos, pos)),
+        };
+
+        if let Err(ref e) = result {
+            observer.on_error(e);
+        }
+        result
+    }
+
+    /// Try to match specification to input and return the input byte range of every
+    /// matched template token.
+    ///
+    /// `MultipleLines` tokens report the range of skipped lines. New lines are not reported.
+    pub fn match_spans<I: Read, P: ParamSource + ?Sized>(
+        &self,
+        input: &mut I,
+        params: &P,
+    ) -> result::Result<Vec<(ast::Match, Range<usize>)>, At<TemplateMatchError>> {
+        let pos = FilePosition::new();
+        let mut contents = Vec::new();
+        input
+            .read_to_end(&mut contents)
+            .map_err(|e| TemplateMatchError::from(e).at(pos, pos))?;
+
+        self.match_bytes_spans(&contents, params)
+    }
+
+    /// Try to match specification to input and return the number of input lines skipped by
+    /// every `MultipleLines` and `CaptureLines` token, in template order.
+    ///
+    /// Of consecutive skip tokens, the first one skips all lines.
+    pub fn match_skipped_lines<I: Read, P: ParamSource + ?Sized>(
+        &self,
+        input: &mut I,
+        params: &P,
+    ) -> result::Result<Vec<usize>, At<TemplateMatchError>> {
+        let pos = FilePosition::new();
+        let mut contents = Vec::new();
+        input
+            .read_to_end(&mut contents)
+            .map_err(|e| TemplateMatchError::from(e).at(pos, pos))?;
+
+        Ok(self
+            .match_bytes_spans(&contents, params)?
+            .into_iter()
+            .filter_map(|(token, range)| match token {
+                ast::Match::MultipleLines
+                | ast::Match::LineRange { .. }
+                | ast::Match::CaptureLines(_) => {
+                    Some(count_lines(&contents[range]))
+                }
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Try to match specification to input and return the lines captured by every
+    /// `CaptureLines` token, by the captured var name.
+    ///
+    /// Captured text includes the line endings of captured lines.
+    pub fn match_captures<I: Read, P: ParamSource + ?Sized>(
+        &self,
+        input: &mut I,
+        params: &P,
+    ) -> result::Result<HashMap<String, String>, At<TemplateMatchError>> {
+        let pos = FilePosition::new();
+        let mut contents = Vec::new();
+        input
+            .read_to_end(&mut contents)
+            .map_err(|e| TemplateMatchError::from(e).at(pos, pos))?;
+
+        let mut captures = HashMap::new();
+        for (token, range) in self.match_bytes_spans(&contents, params)? {
+            if let ast::Match::CaptureLines(key) = token {
+                let text = String::from_utf8_lossy(&contents[range]).into_owned();
+                captures.insert(key, text);
+            }
+        }
+
+        Ok(captures)
+    }
+
+    fn match_bytes_spans<P: ParamSource + ?Sized>(
+        &self,
+        contents: &[u8],
+        params: &P,
+    ) -> result::Result<Vec<(ast::Match, Range<usize>)>, At<TemplateMatchError>> {
+        // every line group reports exactly one event when the whole input matches
+        let mut lines = Vec::new();
+        let options = MatchOptions::default();
+        let errors = {
+            let mut observer = |e: &MatchEvent| lines.push((e.start, e.line_start, e.bytes));
+            self.match_bytes_all(contents, params, &options, true, Some(&mut observer))
+        };
+        let dedent = self.dedent(contents, &options);
+        if let Some(e) = errors.into_iter().next() {
+            return Err(e);
+        }
+
+        let mut spans = Vec::new();
+        let mut line_spans = Vec::new();
+        let mut lines = lines.into_iter();
+        let mut end = FilePosition::start_of(contents).byte;
+        // the first of consecutive skip tokens takes all skipped lines
+        let mut skip_tokens = Vec::new();
+        for state in &self.groups {
+            match *state {
+                MultilineMatchState::MultipleLines(token) => skip_tokens.push(token),
+                MultilineMatchState::Line(ref line) => {
+                    let (start, line_start, bytes) = lines.next().expect("expected line event");
+                    for (i, token) in skip_tokens.drain(..).enumerate() {
+                        let skip_start = if i == 0 { start } else { line_start };
+                        spans.push((token.clone(), skip_start..line_start));
+                    }
+
+                    let pos = FilePosition::new().advanced(line_start);
+                    line_spans.clear();
+                    let matched = line.matches(
+                        pos,
+                        contents,
+                        params,
+                        &options,
+                        &dedent,
+                        Some(&mut line_spans),
+                    );
+                    debug_assert!(matched.is_ok(), "matched line should match again");
+                    spans.extend(line_spans.drain(..).map(|(m, r)| (m.clone(), r)));
+                    end = start + bytes;
+                }
+            }
+        }
+        for (i, token) in skip_tokens.into_iter().enumerate() {
+            let skip_start = if i == 0 { end } else { contents.len() };
+            spans.push((token.clone(), skip_start..contents.len()));
+        }
+
+        Ok(spans)
+    }
+
+    /// Try to match specification to input and return the template line that did not match,
+    /// together with the tokens that matched before it diverged.
+    ///
+    /// If the line follows `MultipleLines`, the input line where most of its tokens matched is
+    /// returned. Returns `None` if contents match or the mismatch is not in a template line.
+    pub fn line_mismatch<'r, P: ParamSource + ?Sized>(
+        &'r self,
+        contents: &[u8],
+        params: &P,
+    ) -> Option<LineMismatch<'r>> {
+        let mut end = FilePosition::start_of(contents).byte;
+        let mut matched_lines = 0;
+        let options = MatchOptions::default();
+        let errors = {
+            let mut observer = |e: &MatchEvent| {
+                end = e.start + e.bytes;
+                matched_lines += 1;
+            };
+            self.match_bytes_all(contents, params, &options, true, Some(&mut observer))
+        };
+        if errors.is_empty() {
+            return None;
+        }
+
+        let mut skip_lines_state = false;
+        let mut failed = None;
+        for state in &self.groups {
+            match *state {
+                MultilineMatchState::MultipleLines(_) => skip_lines_state = true,
+                MultilineMatchState::Line(ref line) => {
+                    if matched_lines == 0 {
+                        failed = Some(line);
+                        break;
+                    }
+                    matched_lines -= 1;
+                    skip_lines_state = false;
+                }
+            }
+        }
+        let line = failed?;
+
+        let mut pos = FilePosition::start_of(contents);
+        let mut eol_pos = pos;
+        update_eol(&pos, &mut eol_pos, contents);
+        while pos.byte < end {
+            skip_line(&mut pos, &mut eol_pos, contents);
+        }
+
+        let dedent = self.dedent(contents, &options);
+        let mut best: Option<LineMismatch> = None;
+        loop {
+            let mut spans = Vec::new();
+            let matched = line.matches(pos, contents, params, &options, &dedent, Some(&mut spans));
+            if let Err(e) = matched {
+                let closer = match best {
+                    Some(ref b) => spans.len() > b.matched.len(),
+                    None => true,
+                };
+                if closer {
+                    best = Some(LineMismatch {
+                        tokens: &line.tokens,
+                        matched: spans,
+                        line_start: pos.byte,
+                        err: line_group_error(e, contents, eol_pos),
+                    });
+                }
+            }
+            if !skip_lines_state || eol_pos.byte >= contents.len() {
+                break;
+            }
+            skip_line(&mut pos, &mut eol_pos, contents);
+        }
+
+        best
+    }
+
+    /// Try to match specification to input and return all errors instead of the first one.
+    ///
+    /// After a line mismatch, matching continues from the next input line with the next
+    /// template line.
+    pub fn match_contents_all<I: Read, P: ParamSource + ?Sized>(
+        &self,
+        input: &mut I,
+        params: &P,
+    ) -> Vec<At<TemplateMatchError>> {
+        let pos = FilePosition::new();
+        let mut contents = Vec::new();
+        if let Err(e) = input.read_to_end(&mut contents) {
+            return vec![TemplateMatchError::from(e).at(pos, pos)];
+        }
+
+        self.match_bytes_all(&contents, params, &MatchOptions::default(), false, None)
+    }
+
+    /// Try to match specification to input and return only errors that are not in `baseline`.
+    ///
+    /// Errors are compared by kind, position and text.
+    pub fn match_against_baseline<I: Read, P: ParamSource + ?Sized>(
+        &self,
+        input: &mut I,
+        params: &P,
+        baseline: &[At<TemplateMatchError>],
+    ) -> Vec<At<TemplateMatchError>> {
+        self.match_contents_all(input, params)
+            .into_iter()
+            .filter(|e| {
+                !baseline
+                    .iter()
+                    .any(|b| b.desc == e.desc && b.lo == e.lo && b.hi == e.hi)
+            })
+            .collect()
+    }
+
+    /// Finds the common indentation of template and input lines if `dedent` is enabled.
+    fn dedent<'c>(&'c self, contents: &[u8], options: &MatchOptions) -> Dedent<'c> {
+        if !options.dedent {
+            return Dedent::default();
+        }
+
+        let template = common_indent(self.groups.iter().filter_map(|state| match *state {
+            MultilineMatchState::Line(ref line) => match line.tokens.first() {
+                Some(&&ast::Match::Text(ref text)) => {
+                    let blank = line.tokens.len() == 1 && text.bytes().all(is_blank);
+                    if blank {
+                        None
+                    } else {
+                        Some(text.as_bytes())
+                    }
+                }
+                Some(_) => Some(&b""[..]),
+                None => None,
+            },
+            MultilineMatchState::MultipleLines(_) => None,
+        }));
+        let input = common_indent(
+            contents
+                .split(|b| *b == b'\n' || *b == b'\r')
+                .filter(|line| !line.iter().all(|b| is_blank(*b))),
+        );
+
+        Dedent {
+            template: template,
+            input: input.len(),
+        }
+    }
+
+    /// Returns `BlockMismatch` for the block that contains the line group at `group`, if the
+    /// block has more than one line.
+    fn block_mismatch<P: ParamSource + ?Sized>(
+        &self,
+        group: usize,
+        start: FilePosition,
+        contents: &[u8],
+        params: &P,
+    ) -> Option<At<TemplateMatchError>> {
+        let is_line = |state: &MultilineMatchState| match *state {
+            MultilineMatchState::Line(_) => true,
+            MultilineMatchState::MultipleLines(_) => false,
+        };
+        let first = self.groups[..group]
+            .iter()
+            .rposition(|state| !is_line(state))
+            .map_or(0, |i| i + 1);
+        let mut end = self.groups[group..]
+            .iter()
+            .position(|state| !is_line(state))
+            .map_or(self.groups.len(), |i| group + i);
+        // the final new line of the template ends the last line instead of starting a new one
+        if end == self.groups.len() && end > group + 1 {
+            if let MultilineMatchState::Line(ref line) = self.groups[end - 1] {
+                if line.tokens.is_empty() {
+                    end -= 1;
+                }
+            }
+        }
+        if end - first < 2 {
+            return None;
+        }
+
+        let expected: Vec<_> = self.groups[first..end]
+            .iter()
+            .filter_map(|state| match *state {
+                MultilineMatchState::Line(ref line) => Some(line.expected_text(params)),
+                MultilineMatchState::MultipleLines(_) => None,
+            })
+            .collect();
+
+        // the error points at the first mismatching line, or the end of input
+        let mut found = Vec::new();
+        let mut pos = start;
+        let mut eol_pos = start;
+        let mut span = None;
+        update_eol(&pos, &mut eol_pos, contents);
+        while found.len() < expected.len() && pos.byte < contents.len() {
+            let line = String::from_utf8_lossy(&contents[pos.byte..eol_pos.byte]).into_owned();
+            if span.is_none() && line != expected[found.len()] {
+                span = Some((pos, eol_pos));
+            }
+            found.push(line);
+            skip_line(&mut pos, &mut eol_pos, contents);
+        }
+        let (lo, hi) = span.unwrap_or((pos, pos));
+
+        Some(
+            TemplateMatchError::BlockMismatch {
+                expected: expected,
+                found: found,
+                first_line: start.line,
+            }.at(lo, hi),
+        )
+    }
+
+    fn match_bytes_all<P: ParamSource + ?Sized>(
+        &self,
+        contents: &[u8],
+        params: &P,
+        options: &MatchOptions,
+        first_only: bool,
+        mut observer: Option<&mut dyn MatchObserver>,
+    ) -> Vec<At<TemplateMatchError>> {
+        let mut errors = Vec::new();
+        let mut pos = FilePosition::start_of(contents);
+        let content_start = pos.byte;
+        let mut eol_pos = pos;
+        let dedent = self.dedent(contents, options);
+
+        let mut skip_lines_state = !options.anchored;
+        // lines skipped before the template when it is not anchored
+        let mut implicit_skip = !options.anchored;
+        let mut had_new_line = true;
+        let mut prev_blank = false;
+        // where the first line of the current block matched
+        let mut block_start = None;
+        // lines skipped by the current skip token, and the most lines it can skip
+        let mut skipped = 0;
+        let mut skip_max = None;
+        update_eol(&pos, &mut eol_pos, contents);
+
+        match_debug!(
+            "matching {} line groups against {} bytes",
+            self.groups.len(),
+            contents.len()
+        );
+
+        for (i, state) in self.groups.iter().enumerate() {
+            match *state {
+                MultilineMatchState::MultipleLines(token) => {
+                    match_trace!("{}: {:?} starts skipping lines", pos, state);
+                    skip_lines_state = true;
+                    implicit_skip = false;
+                    prev_blank = false;
+                    block_start = None;
+                    skipped = 0;
+                    skip_max = match *token {
+                        ast::Match::LineRange { max } => Some(max),
+                        _ => None,
+                    };
+                }
+                MultilineMatchState::Line(ref line) => {
+                    // the last group is the end of the template, not a blank line
+                    let blank = options.collapse_blank_lines
+                        && line.tokens.is_empty()
+                        && i + 1 < self.groups.len();
+                    if blank && prev_blank {
+                        continue;
+                    }
+                    // the final new line of a template that is not anchored can be followed
+                    // by anything
+                    if !options.anchored && i + 1 == self.groups.len() && line.tokens.is_empty()
+                        && had_new_line && !skip_lines_state
+                    {
+                        continue;
+                    }
+                    if options.ignore_final_newline && i + 1 == self.groups.len()
+                        && line.tokens.is_empty() && !had_new_line
+                        && pos.byte >= contents.len()
+                    {
+                        continue;
+                    }
+                    prev_blank = blank;
+                    match_trace!("{}: matching line {:?}", pos, line.tokens);
+
+                    let start = pos.byte;
+                    // timing is only needed by observers
+                    let started = observer.as_ref().map(|_| Instant::now());
+                    // the line that would have matched if no lines were skipped
+                    let mut empty_skip_pos = None;
+                    if skip_lines_state && options.skip_at_least_one_line && !implicit_skip {
+                        if pos.byte >= contents.len() {
+                            errors.push(TemplateMatchError::ExpectedSkippedLine.at(pos, pos));
+                            return errors;
+                        }
+                        if line.matches(pos, contents, params, options, &dedent, None).is_ok() {
+                            empty_skip_pos = Some(pos);
+                        }
+                        match_trace!("{}: skipping line to skip at least one", pos);
+                        if let Some(ref mut observer) = observer {
+                            observer.on_skip_line(pos);
+                        }
+                        skip_line(&mut pos, &mut eol_pos, contents);
+                        skipped += 1;
+                    }
+                    'text: loop {
+                        let pos_byte = pos.byte;
+                        match line.matches(pos, contents, params, options, &dedent, None) {
+                            Ok((bytes, end_bytes)) => {
+                                if bytes == 0 && !had_new_line {
+                                    errors.push(TemplateMatchError::ExpectedEol.at(pos, pos));
+                                    return errors;
+                                }
+                                if block_start.is_none() {
+                                    block_start = Some(pos);
+                                }
+
+                                pos.advance(bytes);
+                                pos.next_line(end_bytes);
+                                had_new_line = end_bytes > 0;
+                                skip_lines_state = false;
+                                implicit_skip = false;
+                                if blank && had_new_line {
+                                    skip_blank_lines(&mut pos, contents);
+                                }
+                                update_eol(&pos, &mut eol_pos, contents);
+                                match_trace!("{}: matched line {:?}", pos, line.tokens);
+
+                                if let Some(ref mut observer) = observer {
+                                    observer.on_line_matched(&MatchEvent {
+                                        tokens: &line.tokens,
+                                        start: start,
+                                        line_start: pos_byte,
+                                        bytes: pos.byte - start,
+                                        elapsed: started.map(|s| s.elapsed()).unwrap_or_default(),
+                                    });
+                                }
+
+                                break 'text;
                             }
+                            Err(err_match) => if skip_lines_state {
+                                if let Some(max) = skip_max {
+                                    if skipped >= max {
+                                        errors.push(
+                                            TemplateMatchError::ExpectedMatchWithin { max: max }
+                                                .at(pos, eol_pos),
+                                        );
+                                        return errors;
+                                    }
+                                }
+
+                                if pos_byte >= contents.len() {
+                                    if let Some(empty_skip_pos) = empty_skip_pos {
+                                        errors.push(
+                                            TemplateMatchError::ExpectedSkippedLine
+                                                .at(empty_skip_pos, empty_skip_pos),
+                                        );
+                                        return errors;
+                                    }
+                                    errors.push(match err_match {
+                                        LineGroupMatchErr::Text { pos: err_pos, text } => {
+                                            TemplateMatchError::ExpectedTextFoundEof {
+                                                expected: text.to_string(),
+                                                found: last_line(contents),
+                                            }.at(err_pos, eol_pos)
+                                        }
+                                        other => line_group_error(other, contents, eol_pos),
+                                    });
+                                    return errors;
+                                }
+
+                                match_trace!("{}: skipping line that does not match", pos);
+                                if let Some(ref mut observer) = observer {
+                                    observer.on_skip_line(pos);
+                                }
+                                skip_line(&mut pos, &mut eol_pos, contents);
+                                skipped += 1;
+
+                                continue 'text;
+                            } else {
+                                let block = match err_match {
+                                    LineGroupMatchErr::ParamNotFound { .. } => None,
+                                    _ if options.report_block_mismatch => self.block_mismatch(
+                                        i,
+                                        block_start.unwrap_or(pos),
+                                        contents,
+                                        params,
+                                    ),
+                                    _ => None,
+                                };
+                                let err = match block {
+                                    Some(err) => err,
+                                    None => line_group_error(err_match, contents, eol_pos),
+                                };
+                                match_debug!(
+                                    "{}: line {:?} failed: {}",
+                                    pos,
+                                    line.tokens,
+                                    err.desc
+                                );
+                                errors.push(err);
+                                if first_only {
+                                    return errors;
+                                }
+
+                                // skip the mismatched input line and continue with the next one
+                                let newline_bytes =
+                                    matches_newline(&eol_pos, contents).expect("expected newline");
+                                if newline_bytes == 0 {
+                                    return errors;
+                                }
+                                pos.advance(eol_pos.byte - pos.byte);
+                                pos.next_line(newline_bytes);
+                                had_new_line = true;
+                                update_eol(&pos, &mut eol_pos, contents);
+
+                                break 'text;
+                            },
+                        }
+                    }
+                }
+            }
+        }
+
+        if skip_lines_state {
+            if options.skip_at_least_one_line && !implicit_skip && pos.byte >= contents.len() {
+                errors.push(TemplateMatchError::ExpectedSkippedLine.at(pos, pos));
+            } else if let (Some(max), true) = (skip_max, options.anchored) {
+                // the template ends with a skip that takes at most `max` lines
+                while skipped < max && pos.byte < contents.len() {
+                    skip_line(&mut pos, &mut eol_pos, contents);
+                    skipped += 1;
+                }
+                if pos.byte < contents.len() {
+                    errors.push(TemplateMatchError::ExpectedEof.at(pos, eol_pos));
+                }
+            }
+        } else if options.anchored {
+            // the start of input counts as a new line, so input that is empty or has only
+            // a byte order mark is matched by an empty template
+            if pos.byte < contents.len() || (had_new_line && contents.len() > content_start) {
+                errors.push(TemplateMatchError::ExpectedEof.at(pos, pos));
+            }
+        }
 
-                            pos.advance(eol_pos.byte - pos_byte);
-                            pos.next_line(
-                                matches_newline(&eol_pos, &contents).expect("expected newline"),
-                            );
-                            update_eol(&pos, &mut eol_pos, &contents);
+        errors
+    }
+}
 
-                            continue 'text;
-                        } else {
-                            match err_match {
-                                LineGroupMatchErr::Text { pos, text } => {
-                                    return Err(TemplateMatchError::ExpectedText {
-                                        expected: text.to_string(),
-                                        found: String::from_utf8_lossy(
-                                            &contents[pos.byte..eol_pos.byte],
-                                        ).into_owned(),
-                                    }.at(pos, eol_pos))
-                                }
-                                LineGroupMatchErr::ParamNotFound { pos, key } => {
-                                    return Err(TemplateMatchError::MissingParam(key.into()).at(pos, pos))
-                                }
-                                LineGroupMatchErr::NewLineOrEof { pos } => {
-                                    return Err(TemplateMatchError::ExpectedEol.at(pos, pos))
-                                }
-                            }
-                        },
-                    }
-                },
-            }
+/// Matches one specification item against many inputs, reusing the read buffer.
+///
+/// Created with `Matcher::new`. Every match reads the whole input into the same buffer, so
+/// after the first few inputs matching allocates only for the match itself.
+#[derive(Debug)]
+pub struct Matcher<'s> {
+    item: CompiledItem<'s>,
+    options: MatchOptions,
+    buffer: Vec<u8>,
+}
+
+impl<'s> Matcher<'s> {
+    /// Creates a matcher for the item with default match options.
+    pub fn new(item: &Item<'s>) -> Matcher<'s> {
+        Matcher {
+            item: item.compile(),
+            options: MatchOptions::default(),
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Uses `options` for all following matches.
+    pub fn options(mut self, options: MatchOptions) -> Matcher<'s> {
+        self.options = options;
+        self
+    }
+
+    /// Try to match the item to input and return any errors if they don't match.
+    pub fn match_reader<I: Read, P: ParamSource + ?Sized>(
+        &mut self,
+        input: &mut I,
+        params: &P,
+    ) -> result::Result<(), At<TemplateMatchError>> {
+        self.buffer.clear();
+        if let Err(e) = input.read_to_end(&mut self.buffer) {
+            let pos = FilePosition::new();
+            return Err(TemplateMatchError::from(e).at(pos, pos));
         }
 
-        if !skip_lines_state {
-            if pos.byte < contents.len() || (had_new_line && contents.len() > 0) {
-                return Err(TemplateMatchError::ExpectedEof.at(pos, pos));
+        self.item.match_bytes_with(&self.buffer, params, &self.options)
+    }
+
+    /// Try to match the item to the file at `path`.
+    ///
+    /// A file that can not be opened fails with `TemplateMatchError::Io`.
+    pub fn match_file<P: ParamSource + ?Sized>(
+        &mut self,
+        path: &Path,
+        params: &P,
+    ) -> result::Result<(), At<TemplateMatchError>> {
+        match File::open(path) {
+            Ok(mut file) => self.match_reader(&mut file, params),
+            Err(e) => {
+                let pos = FilePosition::new();
+                Err(TemplateMatchError::from(e).at(pos, pos))
             }
         }
+    }
+}
 
-        Ok(())
+/// Returns an error at the first line ending in contents that is not allowed by `expected`.
+fn check_line_ending(
+    contents: &[u8],
+    expected: LineEnding,
+) -> result::Result<(), At<TemplateMatchError>> {
+    let (byte, found, len) = match line_ending::find_wrong_line_ending(contents, expected) {
+        Some(wrong) => wrong,
+        None => return Ok(()),
+    };
+
+    let eol_pos = position_at(contents, byte);
+    Err(TemplateMatchError::WrongLineEnding {
+        expected: expected,
+        found: found,
+    }.at(eol_pos, eol_pos.advanced(len)))
+}
+
+fn check_trailing_newline(
+    contents: &[u8],
+    expected: bool,
+) -> result::Result<(), At<TemplateMatchError>> {
+    let start = FilePosition::start_of(contents).byte;
+    if start == contents.len() {
+        return Ok(());
+    }
+
+    let mut text_end = contents.len();
+    let mut count = 0;
+    let mut first_len = 0;
+    while text_end > start {
+        let len = if contents[start..text_end].ends_with(b"\r\n") {
+            2
+        } else if contents[text_end - 1] == b'\n' || contents[text_end - 1] == b'\r' {
+            1
+        } else {
+            break;
+        };
+        text_end -= len;
+        count += 1;
+        first_len = len;
+    }
+
+    let wrong_from = if expected && count == 0 {
+        contents.len()
+    } else if expected && count > 1 {
+        text_end + first_len
+    } else if !expected && count > 0 {
+        text_end
+    } else {
+        return Ok(());
+    };
+
+    // point at the first wrong newline only, so the error stays on one line
+    let pos = position_at(contents, wrong_from);
+    Err(TemplateMatchError::WrongTrailingNewline { expected: expected }.at(pos, pos))
+}
+
+/// Returns the position of a byte that is at the start or the end of a line.
+fn position_at(contents: &[u8], byte: usize) -> FilePosition {
+    let mut pos = FilePosition::start_of(contents);
+    let mut eol_pos = pos;
+    update_eol(&pos, &mut eol_pos, contents);
+    while eol_pos.byte < byte {
+        skip_line(&mut pos, &mut eol_pos, contents);
+    }
+    pos.advanced(byte - pos.byte)
+}
+
+/// Receives the progress of `Item::match_contents_observed`.
+///
+/// All methods do nothing by default.
+pub trait MatchObserver {
+    /// Called after a template line matched.
+    fn on_line_matched(&mut self, _event: &MatchEvent) {}
+
+    /// Called before an input line is skipped by `MultipleLines`, with the start of that line.
+    fn on_skip_line(&mut self, _pos: FilePosition) {}
+
+    /// Called with the error that stopped matching.
+    fn on_error(&mut self, _err: &At<TemplateMatchError>) {}
+}
+
+/// Reports matched lines to a closure.
+impl<F: FnMut(&MatchEvent)> MatchObserver for F {
+    fn on_line_matched(&mut self, event: &MatchEvent) {
+        self(event)
+    }
+}
+
+/// A matched template line, reported to `MatchObserver`.
+#[derive(Debug)]
+pub struct MatchEvent<'a> {
+    /// Template tokens of the line.
+    pub tokens: &'a [&'a ast::Match],
+    /// Input byte at which matching of the line started.
+    pub start: usize,
+    /// Input byte at which the matched line starts, after lines skipped before it.
+    pub line_start: usize,
+    /// Number of matched input bytes, including lines skipped before it and the new line.
+    pub bytes: usize,
+    /// Time spent matching the line.
+    pub elapsed: Duration,
+}
+
+/// A template line that did not match, returned by `CompiledItem::line_mismatch`.
+#[derive(Debug)]
+pub struct LineMismatch<'a> {
+    /// Template tokens of the line.
+    pub tokens: &'a [&'a ast::Match],
+    /// Input ranges of the tokens that matched, in template order.
+    ///
+    /// The first token after them that is not a `Gap` is the one that diverged.
+    pub matched: Vec<(&'a ast::Match, Range<usize>)>,
+    /// Input byte at which the line starts.
+    pub line_start: usize,
+    /// The error of the diverging token.
+    pub err: At<TemplateMatchError>,
+}
+
+fn line_group_error(
+    err_match: LineGroupMatchErr,
+    contents: &[u8],
+    eol_pos: FilePosition,
+) -> At<TemplateMatchError> {
+    match err_match {
+        LineGroupMatchErr::Text { pos, text } => TemplateMatchError::ExpectedText {
+            expected: text.to_string(),
+            found: String::from_utf8_lossy(&contents[pos.byte..eol_pos.byte]).into_owned(),
+            found_line: String::from_utf8_lossy(&contents[pos.byte - pos.col..eol_pos.byte])
+                .into_owned(),
+        }.at(pos, eol_pos),
+        LineGroupMatchErr::ParamNotFound { pos, key } => {
+            TemplateMatchError::MissingParam(key.into()).at(pos, pos)
+        }
+        LineGroupMatchErr::NewLineOrEof { pos } => TemplateMatchError::ExpectedEol.at(pos, pos),
+        LineGroupMatchErr::Indent {
+            pos,
+            expected,
+            found_bytes,
+        } => TemplateMatchError::ExpectedIndent {
+            expected: expected,
+            found: String::from_utf8_lossy(&contents[pos.byte..pos.byte + found_bytes])
+                .into_owned(),
+        }.at(pos, pos.advanced(found_bytes)),
     }
 }
 
@@ -276,7 +2086,8 @@ impl<'s> Item<'s> {
 /// is different than line match.
 #[derive(Debug)]
 enum MultilineMatchState<'a> {
-    MultipleLines,
+    /// `MultipleLines`, `LineRange` or `CaptureLines` token.
+    MultipleLines(&'a ast::Match),
     Line(LineGroup<'a>),
 }
 
@@ -285,6 +2096,7 @@ enum LineGroupMatchErr<'a> {
     Text { pos: FilePosition, text: &'a str },
     ParamNotFound { pos: FilePosition, key: &'a str },
     NewLineOrEof { pos: FilePosition },
+    Indent { pos: FilePosition, expected: usize, found_bytes: usize },
 }
 
 /// All tokens for a line.
@@ -300,22 +2112,45 @@ impl<'a> LineGroup<'a> {
 
     /// Check if a line match template tokens `MultipleLines` and `NewLine` are handled by the
     /// called that separated tokens into lines.
-    pub fn matches<'o, 'r>(
+    ///
+    /// If `spans` are given, the input range of every token is pushed to them.
+    pub fn matches<'o, 'r, P: ParamSource + ?Sized>(
         &'a self,
         mut pos: FilePosition,
         content: &'o [u8],
-        params: &HashMap<&str, &'r str>,
+        params: &'r P,
+        options: &MatchOptions,
+        dedent: &Dedent,
+        mut spans: Option<&mut Vec<(&'a ast::Match, Range<usize>)>>,
     ) -> result::Result<(usize, usize), LineGroupMatchErr<'r>>
     where
         'a: 'r,
     {
         let start_pos = pos;
+        let indent = content[pos.byte..]
+            .iter()
+            .take(dedent.input)
+            .take_while(|b| **b == b' ' || **b == b'\t')
+            .count();
+        pos.advance(indent);
+        let mut gap = 0;
+        let mut gap_first = 0;
+        let mut gap_byte = pos.byte;
 
-        for token in &self.tokens {
+        for (i, token) in self.tokens.iter().enumerate() {
+            let token_start;
             match **token {
                 ast::Match::Text(ref text) => {
-                    if let Some(bytes) = matches_content(&pos, content, text.as_bytes()) {
-                        pos.advance(bytes);
+                    let text = if i == 0 && text.as_bytes().starts_with(dedent.template) {
+                        &text[dedent.template.len()..]
+                    } else {
+                        &text[..]
+                    };
+                    if let Some((skipped, bytes)) =
+                        matches_content_within(&pos, content, text.as_bytes(), gap, options)
+                    {
+                        token_start = pos.byte + skipped;
+                        pos.advance(skipped + bytes);
                     } else {
                         return Err(LineGroupMatchErr::Text {
                             pos: pos,
@@ -323,10 +2158,13 @@ impl<'a> LineGroup<'a> {
                         });
                     }
                 }
-                ast::Match::Var(ref key) => match params.get(&key[..]) {
-                    Some(ref text) => {
-                        if let Some(bytes) = matches_content(&pos, content, text.as_bytes()) {
-                            pos.advance(bytes);
+                ast::Match::Var(ref key) => match params.get(key) {
+                    Some(text) => {
+                        if let Some((skipped, bytes)) =
+                            matches_content_within(&pos, content, text.as_bytes(), gap, options)
+                        {
+                            token_start = pos.byte + skipped;
+                            pos.advance(skipped + bytes);
                         } else {
                             return Err(LineGroupMatchErr::Text {
                                 pos: pos,
@@ -341,74 +2179,532 @@ impl<'a> LineGroup<'a> {
                         })
                     }
                 },
-                ast::Match::MultipleLines => unreachable!(),
+                ast::Match::Indent(expected) => {
+                    let found_bytes = content[pos.byte..]
+                        .iter()
+                        .take_while(|b| **b == b' ' || **b == b'\t')
+                        .count();
+                    let is_exact = found_bytes == expected
+                        && content[pos.byte..pos.byte + found_bytes]
+                            .iter()
+                            .all(|b| *b == b' ');
+                    if !is_exact {
+                        return Err(LineGroupMatchErr::Indent {
+                            pos: pos,
+                            expected: expected,
+                            found_bytes: found_bytes,
+                        });
+                    }
+                    token_start = pos.byte;
+                    pos.advance(found_bytes);
+                }
+                ast::Match::Gap(len) => {
+                    if gap == 0 {
+                        gap_first = i;
+                        gap_byte = pos.byte;
+                    }
+                    gap += len;
+                    continue;
+                }
+                ast::Match::MultipleLines
+                | ast::Match::LineRange { .. }
+                | ast::Match::CaptureLines(_) => unreachable!(),
                 ast::Match::NewLine => unreachable!(),
             }
+
+            if let Some(ref mut spans) = spans {
+                if gap > 0 {
+                    self.push_gap_spans(spans, gap_first..i, gap_byte..token_start);
+                }
+                spans.push((*token, token_start..pos.byte));
+            }
+            gap = 0;
+        }
+
+        let line_len = line_remainder_len(&pos, content);
+        for skipped in 0..(gap.min(line_len) + 1) {
+            let gap_pos = pos.advanced(skipped);
+            if let Some(newline_bytes) = matches_newline(&gap_pos, content) {
+                if let Some(ref mut spans) = spans {
+                    if gap > 0 {
+                        let tokens = gap_first..self.tokens.len();
+                        self.push_gap_spans(spans, tokens, gap_byte..gap_pos.byte);
+                    }
+                }
+                return Ok((gap_pos.byte - start_pos.byte, newline_bytes));
+            }
+        }
+
+        Err(LineGroupMatchErr::NewLineOrEof { pos: pos })
+    }
+
+    /// Returns the line as it is expected in the input, with var values substituted.
+    ///
+    /// Missing vars are written as they are in the template.
+    pub fn expected_text<P: ParamSource + ?Sized>(&self, params: &P) -> String {
+        let mut text = String::new();
+        for token in &self.tokens {
+            match **token {
+                ast::Match::Var(ref key) => match params.get(key) {
+                    Some(value) => text.push_str(value),
+                    None => text.push_str(&token.to_string()),
+                },
+                ref other => text.push_str(&other.to_string()),
+            }
         }
+        text
+    }
 
-        match matches_newline(&pos, content) {
-            Some(newline_bytes) => Ok((pos.byte - start_pos.byte, newline_bytes)),
-            None => Err(LineGroupMatchErr::NewLineOrEof { pos: pos }),
+    /// Pushes the same input range for every gap token in `tokens`.
+    fn push_gap_spans(
+        &'a self,
+        spans: &mut Vec<(&'a ast::Match, Range<usize>)>,
+        tokens: Range<usize>,
+        bytes: Range<usize>,
+    ) {
+        for token in &self.tokens[tokens] {
+            spans.push((*token, bytes.clone()));
         }
     }
 }
 
-fn matches_content(pos: &FilePosition, content: &[u8], to_match: &[u8]) -> Option<usize> {
-    if content[pos.byte..].starts_with(to_match) {
+fn matches_content(
+    pos: &FilePosition,
+    content: &[u8],
+    to_match: &[u8],
+    options: &MatchOptions,
+) -> Option<usize> {
+    let rest = content.get(pos.byte..)?;
+
+    if options.normalize_whitespace {
+        return matches_content_normalized(rest, to_match);
+    }
+
+    if rest.starts_with(to_match) {
         return Some(to_match.len());
     }
 
     None
 }
 
+/// Common indentation removed from lines when matching with `dedent`.
+#[derive(Debug, Default)]
+struct Dedent<'a> {
+    /// Indentation removed from the first text of every template line.
+    template: &'a [u8],
+    /// Number of indentation bytes skipped at the start of every input line.
+    input: usize,
+}
+
+fn is_blank(b: u8) -> bool {
+    b == b' ' || b == b'\t'
+}
+
+/// Returns the longest run of spaces and tabs that starts every line.
+fn common_indent<'a, I: Iterator<Item = &'a [u8]>>(lines: I) -> &'a [u8] {
+    let mut common: Option<&[u8]> = None;
+    for line in lines {
+        let indent = &line[..line.iter().take_while(|b| is_blank(**b)).count()];
+        common = Some(match common {
+            None => indent,
+            Some(common) => {
+                let len = common
+                    .iter()
+                    .zip(indent.iter())
+                    .take_while(|&(a, b)| a == b)
+                    .count();
+                &common[..len]
+            }
+        });
+    }
+    common.unwrap_or(b"")
+}
+
+/// Match content where any run of spaces and tabs in `to_match` matches a run of any length.
+fn matches_content_normalized(content: &[u8], to_match: &[u8]) -> Option<usize> {
+    let is_blank = |b: u8| b == b' ' || b == b'\t';
+    let (mut i, mut j) = (0, 0);
+
+    while j < to_match.len() {
+        if is_blank(to_match[j]) {
+            if i >= content.len() || !is_blank(content[i]) {
+                return None;
+            }
+            while j < to_match.len() && is_blank(to_match[j]) {
+                j += 1;
+            }
+            while i < content.len() && is_blank(content[i]) {
+                i += 1;
+            }
+        } else {
+            if i >= content.len() || content[i] != to_match[j] {
+                return None;
+            }
+            i += 1;
+            j += 1;
+        }
+    }
+
+    Some(i)
+}
+
+/// Try to match content at any offset up to `gap` bytes without crossing the end of line.
+///
+/// Returns the number of skipped bytes and the number of matched bytes.
+fn matches_content_within(
+    pos: &FilePosition,
+    content: &[u8],
+    to_match: &[u8],
+    gap: usize,
+    options: &MatchOptions,
+) -> Option<(usize, usize)> {
+    let max_skip = gap.min(line_remainder_len(pos, content));
+    for skipped in 0..(max_skip + 1) {
+        if let Some(bytes) = matches_content(&pos.advanced(skipped), content, to_match, options) {
+            return Some((skipped, bytes));
+        }
+    }
+
+    None
+}
+
+/// Reads at most `max_lines` lines from input, together with their line endings.
+fn read_lines<R: BufRead>(
+    input: &mut R,
+    max_lines: usize,
+    contents: &mut Vec<u8>,
+) -> io::Result<()> {
+    let mut lines = 0;
+    let mut after_cr = false;
+    loop {
+        let (consumed, done) = {
+            let buf = input.fill_buf()?;
+            if buf.is_empty() {
+                return Ok(());
+            }
+
+            let mut consumed = 0;
+            let mut done = false;
+            for b in buf {
+                if after_cr {
+                    after_cr = false;
+                    if *b == b'\n' {
+                        consumed += 1;
+                        continue;
+                    }
+                }
+                if lines == max_lines {
+                    done = true;
+                    break;
+                }
+                consumed += 1;
+                if *b == b'\n' {
+                    lines += 1;
+                } else if *b == b'\r' {
+                    lines += 1;
+                    after_cr = true;
+                }
+            }
+            contents.extend_from_slice(&buf[..consumed]);
+            (consumed, done)
+        };
+        input.consume(consumed);
+        if done {
+            return Ok(());
+        }
+    }
+}
+
+/// Key of the param that includes another specification file.
+const INCLUDE_KEY: &str = "include";
+
+/// Maximum number of nested includes below the parsed specification.
+pub const MAX_INCLUDE_DEPTH: usize = 32;
+
+/// Parses items of the specification at `path`, recursively replacing `include` params.
+///
+/// The `stack` contains paths of the files that are being included.
+fn parse_included<F>(
+    options: Options,
+    contents: &[u8],
+    path: &Path,
+    resolver: &mut F,
+    stack: &mut Vec<PathBuf>,
+) -> result::Result<Vec<ast::Item>, Error>
+where
+    F: FnMut(&Path) -> io::Result<Vec<u8>>,
+{
+    let spec = Spec::parse(options, contents).map_err(|e| Error::from((path.into(), e)))?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    stack.push(path.into());
+
+    let is_include = |p: &ast::Param| {
+        let is_key = if options.case_insensitive_keys {
+            p.key.eq_ignore_ascii_case(INCLUDE_KEY)
+        } else {
+            p.key == INCLUDE_KEY
+        };
+        is_key && p.value.is_some()
+    };
+
+    let mut items = Vec::new();
+    for mut item in spec.ast.items {
+        let (includes, params): (Vec<_>, Vec<_>) = item.params.into_iter().partition(is_include);
+        item.params = params;
+
+        for include in includes {
+            let include_path = dir.join(include.value.unwrap());
+            if stack.contains(&include_path) {
+                return Err(Error::IncludeCycle { path: include_path });
+            }
+            if stack.len() > MAX_INCLUDE_DEPTH {
+                return Err(Error::MaxDepthExceeded {
+                    path: include_path,
+                    max_depth: MAX_INCLUDE_DEPTH,
+                });
+            }
+            let included = resolver(&include_path).map_err(|e| Error::Include {
+                path: path.into(),
+                include: include_path.clone(),
+                span: item.span,
+                err: e,
+            })?;
+            for mut included_item in
+                parse_included(options, &included, &include_path, resolver, stack)?
+            {
+                if included_item.origin.is_none() {
+                    included_item.origin = Some(include_path.clone());
+                }
+                items.push(included_item);
+            }
+        }
+
+        if !item.params.is_empty() || !item.template.is_empty() {
+            items.push(item);
+        }
+    }
+
+    stack.pop();
+    Ok(items)
+}
+
+/// Replaces `\n`, `\t` and `\\` in template texts, splitting texts at new lines.
+fn interpret_escapes(spec: &mut ast::Spec) {
+    for item in &mut spec.items {
+        let template = item.template.drain(..).collect();
+        item.template = unescape_template(template);
+    }
+}
+
+/// Replaces escapes in the texts of a single template.
+pub fn unescape_template(template: Vec<ast::Match>) -> Vec<ast::Match> {
+    let mut unescaped = Vec::with_capacity(template.len());
+    for m in template {
+        match m {
+            ast::Match::Text(text) => push_unescaped(&mut unescaped, &text),
+            other => unescaped.push(other),
+        }
+    }
+    unescaped
+}
+
+fn push_unescaped(template: &mut Vec<ast::Match>, text: &str) {
+    let mut current = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            current.push(c);
+            continue;
+        }
+        match chars.clone().next() {
+            Some('n') => {
+                if !current.is_empty() {
+                    template.push(ast::Match::Text(current));
+                    current = String::new();
+                }
+                template.push(ast::Match::NewLine);
+            }
+            Some('t') => current.push('\t'),
+            Some('\\') => current.push('\\'),
+            _ => {
+                current.push(c);
+                continue;
+            }
+        }
+        chars.next();
+    }
+    if !current.is_empty() {
+        template.push(ast::Match::Text(current));
+    }
+}
+
+/// Splits contents at lines that contain only the `separator`.
+///
+/// Returns the start byte and line of every chunk together with its contents. Separator lines
+/// and their line endings are not included in chunks.
+fn split_chunks<'a>(contents: &'a [u8], separator: &[u8]) -> Vec<(usize, usize, &'a [u8])> {
+    let mut chunks = Vec::new();
+    let (mut chunk_start, mut chunk_line) = (0, 0);
+    let (mut line_start, mut line) = (0, 0);
+
+    while line_start <= contents.len() {
+        let rest = &contents[line_start..];
+        let line_len = find_newline(rest).unwrap_or(rest.len());
+        let newline_len = if rest[line_len..].starts_with(b"\r\n") {
+            2
+        } else if line_len < rest.len() {
+            1
+        } else {
+            0
+        };
+
+        if &rest[..line_len] == separator {
+            chunks.push((chunk_start, chunk_line, &contents[chunk_start..line_start]));
+            chunk_start = line_start + line_len + newline_len;
+            chunk_line = line + 1;
+        }
+
+        if newline_len == 0 {
+            break;
+        }
+        line_start += line_len + newline_len;
+        line += 1;
+    }
+    chunks.push((chunk_start, chunk_line, &contents[chunk_start..]));
+
+    chunks
+}
+
+/// Returns the number of lines in contents, counting a final line without a line ending.
+fn count_lines(contents: &[u8]) -> usize {
+    let mut count = 0;
+    let mut pos = 0;
+    while let Some(i) = find_newline(&contents[pos..]) {
+        pos += i + if contents[pos + i..].starts_with(b"\r\n") { 2 } else { 1 };
+        count += 1;
+    }
+    if pos < contents.len() {
+        count += 1;
+    }
+    count
+}
+
+/// Returns the last line of contents, ignoring the final line ending.
+fn last_line(contents: &[u8]) -> String {
+    let mut end = contents.len();
+    while end > 0 && (contents[end - 1] == b'\n' || contents[end - 1] == b'\r') {
+        end -= 1;
+    }
+    let start = contents[..end]
+        .iter()
+        .rposition(|b| *b == b'\n' || *b == b'\r')
+        .map_or(0, |i| i + 1);
+    String::from_utf8_lossy(&contents[start..end]).into_owned()
+}
+
+/// Advance to the start of the next line, or to the end of contents at the last line.
+fn skip_line(pos: &mut FilePosition, eol_pos: &mut FilePosition, contents: &[u8]) {
+    pos.advance(eol_pos.byte - pos.byte);
+    pos.next_line(matches_newline(eol_pos, contents).expect("expected newline"));
+    update_eol(pos, eol_pos, contents);
+}
+
+/// Advance past lines that contain nothing but a line ending.
+fn skip_blank_lines(pos: &mut FilePosition, content: &[u8]) {
+    while pos.byte < content.len() {
+        match matches_newline(pos, content) {
+            Some(bytes) if bytes > 0 => pos.next_line(bytes),
+            _ => break,
+        }
+    }
+}
+
+fn line_remainder_len(pos: &FilePosition, content: &[u8]) -> usize {
+    let mut eol_pos = *pos;
+    update_eol(pos, &mut eol_pos, content);
+    eol_pos.byte - pos.byte
+}
+
 fn matches_newline(pos: &FilePosition, content: &[u8]) -> Option<usize> {
-    let end = &content[pos.byte..];
+    // a position past the end is at the end of input too
+    let end = content.get(pos.byte..).unwrap_or(&[]);
     if end.is_empty() {
         return Some(0);
     } else if end.starts_with(b"\n") {
         return Some(1);
     } else if end.starts_with(b"\r\n") {
         return Some(2);
+    } else if end.starts_with(b"\r") {
+        return Some(1);
     }
 
     None
 }
 
 fn update_eol(pos: &FilePosition, eol_pos: &mut FilePosition, contents: &[u8]) {
-    let mut eol = pos.byte;
-    loop {
-        if eol >= contents.len() {
-            break;
+    let eol = if pos.byte >= contents.len() {
+        pos.byte
+    } else {
+        match find_newline(&contents[pos.byte..]) {
+            Some(i) => pos.byte + i,
+            None => contents.len(),
         }
+    };
 
-        let slice = &contents[eol..];
-
-        if slice.starts_with(b"\n") || slice.starts_with(b"\r\n") {
-            break;
-        }
+    *eol_pos = pos.advanced(eol - pos.byte);
+}
 
-        eol += 1;
-    }
+/// Finds the first `\n` or `\r`, which is also the start of `\r\n`.
+#[cfg(feature = "memchr")]
+fn find_newline(contents: &[u8]) -> Option<usize> {
+    memchr::memchr2(b'\n', b'\r', contents)
+}
 
-    *eol_pos = pos.advanced(eol - pos.byte);
+/// Finds the first `\n` or `\r`, which is also the start of `\r\n`.
+#[cfg(not(feature = "memchr"))]
+fn find_newline(contents: &[u8]) -> Option<usize> {
+    contents.iter().position(|b| *b == b'\n' || *b == b'\r')
 }
 
 /// Specification item iterator.
 pub struct ItemIter<'a> {
     inner: slice::Iter<'a, ast::Item>,
+    case_insensitive_keys: bool,
+}
+
+impl<'a> ItemIter<'a> {
+    fn item(&self, i: &'a ast::Item) -> Item<'a> {
+        Item {
+            params: &i.params,
+            template: &i.template,
+            case_insensitive_keys: self.case_insensitive_keys,
+            span: i.span,
+        }
+    }
 }
 
 impl<'a> Iterator for ItemIter<'a> {
     type Item = Item<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.inner.next().map(|i| Item {
-            params: &i.params,
-            template: &i.template,
-        })
+        self.inner.next().map(|i| self.item(i))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
     }
 }
 
+impl<'a> DoubleEndedIterator for ItemIter<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|i| self.item(i))
+    }
+}
+
+impl<'a> ExactSizeIterator for ItemIter<'a> {}
+
+impl<'a> FusedIterator for ItemIter<'a> {}
+
 /// Iterator over the specification items that contain a specific key.
 pub struct ItemValuesByKeyIter<'a, 'p> {
     inner: ItemIter<'a>,
@@ -430,3 +2726,67 @@ impl<'a, 'p> Iterator for ItemValuesByKeyIter<'a, 'p> {
         }
     }
 }
+
+/// Iterator over the specification items that have a param value with a specific prefix.
+pub struct ItemValuesByPrefixIter<'a, 'p> {
+    inner: ItemValuesByKeyIter<'a, 'p>,
+    prefix: &'p str,
+}
+
+impl<'a, 'p> Iterator for ItemValuesByPrefixIter<'a, 'p> {
+    type Item = (Item<'a>, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.inner.next() {
+                Some((item, value)) => if value.starts_with(self.prefix) {
+                    return Some((item, value));
+                },
+                None => return None,
+            }
+        }
+    }
+}
+
+/// Iterator over the keys of template vars of an item.
+pub struct VarsIter<'a> {
+    inner: slice::Iter<'a, ast::Match>,
+}
+
+impl<'a> Iterator for VarsIter<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.inner.next() {
+                Some(&ast::Match::Var(ref key)) => return Some(&key[..]),
+                Some(_) => continue,
+                None => return None,
+            }
+        }
+    }
+}
+
+impl<'a> FusedIterator for VarsIter<'a> {}
+
+/// Iterator over the specification items that have a specific param value.
+pub struct ItemsByParamIter<'a, 'p> {
+    inner: ItemIter<'a>,
+    key: &'p str,
+    value: &'p str,
+}
+
+impl<'a, 'p> Iterator for ItemsByParamIter<'a, 'p> {
+    type Item = Item<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.inner.next() {
+                Some(item) => if item.get_param(self.key) == Some(self.value) {
+                    return Some(item);
+                },
+                None => return None,
+            }
+        }
+    }
+}
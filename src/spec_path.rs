@@ -0,0 +1,180 @@
+// Copyright 2017 Nerijus Arlauskas
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use Result;
+use error::{At, FilePosition, TemplateMatchError};
+use params::ParamSource;
+use spec::{CompiledItem, Item, Options, Spec};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::result;
+
+/// Parsed specification at a path.
+///
+/// It is `Send` and `Sync`, so specifications can be matched on other threads.
+#[derive(Debug, Clone)]
+pub struct SpecPath {
+    pub spec: Spec,
+    pub path: PathBuf,
+}
+
+impl SpecPath {
+    /// Returns items that have at least one `file` param, together with all their file values.
+    pub fn items_with_all_files(&self) -> Vec<(Item<'_>, Vec<&str>)> {
+        self.spec
+            .iter()
+            .map(|item| {
+                let files = item.get_params("file");
+                (item, files)
+            })
+            .filter(|&(_, ref files)| !files.is_empty())
+            .collect()
+    }
+
+    /// Matches every item against every file listed in its `file` params.
+    ///
+    /// File paths are resolved relative to `base_dir`. Returns the errors of all files that did
+    /// not match, so the result is empty if every listed file matches.
+    pub fn match_all_files<P: ParamSource + ?Sized>(
+        &self,
+        base_dir: &Path,
+        params: &P,
+    ) -> Vec<(PathBuf, At<TemplateMatchError>)> {
+        run_spec(self, base_dir, params)
+            .into_iter()
+            .filter_map(|outcome| match outcome.result {
+                Ok(()) => None,
+                Err(e) => Some((outcome.path, e)),
+            })
+            .collect()
+    }
+}
+
+/// Result of matching a single spec item against one of its files.
+#[derive(Debug)]
+pub struct ItemOutcome<'a> {
+    /// Spec item that was matched.
+    pub item: Item<'a>,
+    /// Value of the `file` param.
+    pub file: &'a str,
+    /// File path resolved relative to the base dir.
+    pub path: PathBuf,
+    /// Match result.
+    pub result: result::Result<(), At<TemplateMatchError>>,
+}
+
+/// Matches every item of the spec against every file listed in its `file` params.
+///
+/// File paths are resolved relative to `base_dir`. Returns an outcome for each item and file,
+/// in spec order.
+pub fn run_spec<'a, P: ParamSource + ?Sized>(
+    spec_path: &'a SpecPath,
+    base_dir: &Path,
+    params: &P,
+) -> Vec<ItemOutcome<'a>> {
+    run_spec_with(spec_path, base_dir, |compiled, path| match File::open(path) {
+        Ok(mut input) => compiled.match_contents(&mut input, params),
+        Err(e) => {
+            let pos = FilePosition::new();
+            Err(TemplateMatchError::from(e).at(pos, pos))
+        }
+    })
+}
+
+/// Matches every item of the spec against every file listed in its `file` params, taking
+/// file contents from `files` instead of the file system.
+///
+/// File paths are resolved relative to `base_dir` and looked up in `files`. A file that is
+/// not in `files` fails with a `NotFound` io error.
+pub fn run_spec_in_map<'a, P: ParamSource + ?Sized>(
+    spec_path: &'a SpecPath,
+    files: &HashMap<PathBuf, Vec<u8>>,
+    base_dir: &Path,
+    params: &P,
+) -> Vec<ItemOutcome<'a>> {
+    run_spec_with(spec_path, base_dir, |compiled, path| match files.get(path) {
+        Some(contents) => compiled.match_bytes(contents, params),
+        None => {
+            let pos = FilePosition::new();
+            let e = io::Error::new(io::ErrorKind::NotFound, "file is not in the map");
+            Err(TemplateMatchError::from(e).at(pos, pos))
+        }
+    })
+}
+
+fn run_spec_with<'a, F>(
+    spec_path: &'a SpecPath,
+    base_dir: &Path,
+    mut match_file: F,
+) -> Vec<ItemOutcome<'a>>
+where
+    F: FnMut(&CompiledItem, &Path) -> result::Result<(), At<TemplateMatchError>>,
+{
+    let mut outcomes = Vec::new();
+
+    for (item, files) in spec_path.items_with_all_files() {
+        let compiled = item.compile();
+        for file in files {
+            let path = base_dir.join(file);
+            let result = match_file(&compiled, &path);
+            outcomes.push(ItemOutcome {
+                item: item.clone(),
+                file: file,
+                path: path,
+                result: result,
+            });
+        }
+    }
+
+    outcomes
+}
+
+/// Parses specifications from in-memory file contents, for every path that has the extension.
+///
+/// This works like `walk_spec_dir`, but without the file system. Results are sorted by path.
+pub fn parse_specs_from_map<'a, O: Into<Options<'a>>>(
+    files: &HashMap<PathBuf, Vec<u8>>,
+    extension: &str,
+    options: O,
+) -> Vec<Result<SpecPath>> {
+    let options = options.into();
+    let mut paths: Vec<&PathBuf> = files
+        .keys()
+        .filter(|path| has_extension(path, &[extension]))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            Spec::parse(options, &files[path])
+                .map(|spec| SpecPath {
+                    spec: spec,
+                    path: path.clone(),
+                })
+                .map_err(|e| (path.clone(), e).into())
+        })
+        .collect()
+}
+
+/// Returns true if the path has any of the extensions, compared ignoring ASCII case.
+pub fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    let ext = match path.extension() {
+        Some(ext) => ext,
+        None => return false,
+    };
+    if extensions.iter().any(|e| ext == OsStr::new(e)) {
+        return true;
+    }
+    match ext.to_str() {
+        Some(v) => extensions.iter().any(|e| v.eq_ignore_ascii_case(e)),
+        None => false,
+    }
+}
@@ -5,15 +5,26 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
+//! Byte-level parsing helpers used by the specification lexer.
+//!
+//! All functions operate on the whole `input` and a `cursor` that points into it. The check
+//! functions advance the cursor only when they succeed, while the expect functions always
+//! advance it past the consumed bytes. The cursor must never point beyond the end of input.
+
 use error::{FilePosition, LexResult};
 
+/// A slice of input together with its start and end positions.
 pub struct Contents<'a> {
+    /// The bytes between `lo` and `hi`.
     pub slice: &'a [u8],
+    /// The position of the first byte.
     pub lo: FilePosition,
+    /// One byte beyond the last byte.
     pub hi: FilePosition,
 }
 
 impl<'a> Contents<'a> {
+    /// Creates contents for the `input` bytes between `lo` and `hi` positions.
     pub fn new<'r>(input: &'r [u8], lo: FilePosition, hi: FilePosition) -> Contents<'r> {
         Contents {
             slice: &input[lo.byte..hi.byte],
@@ -22,6 +33,7 @@ impl<'a> Contents<'a> {
         }
     }
 
+    /// Returns contents without leading and trailing spaces and tabs, with adjusted positions.
     pub fn trimmed(self) -> Contents<'a> {
         let mut start = 0;
         let mut end = self.slice.len();
@@ -46,20 +58,32 @@ impl<'a> Contents<'a> {
             hi: self.lo.advanced(end),
         }
     }
+
+    /// Returns contents without `len` bytes on both sides, with adjusted positions.
+    pub fn stripped(self, len: usize) -> Contents<'a> {
+        let end = self.slice.len() - len;
+        Contents {
+            slice: &self.slice[len..end],
+            lo: self.lo.advanced(len),
+            hi: self.lo.advanced(end),
+        }
+    }
 }
 
+/// Checks if there is a `\n`, `\r\n` or `\r` at the cursor and moves the cursor to the next line.
 pub fn check_new_line(cursor: &mut FilePosition, input: &[u8]) -> bool {
     if input[cursor.byte..].starts_with(b"\r\n") {
         cursor.next_line(2);
         return true;
     }
-    if input[cursor.byte..].starts_with(b"\n") {
+    if input[cursor.byte..].starts_with(b"\n") || input[cursor.byte..].starts_with(b"\r") {
         cursor.next_line(1);
         return true;
     }
     return false;
 }
 
+/// Checks if the input at the cursor starts with `other` bytes and moves the cursor past them.
 pub fn check_exact_bytes<'e>(cursor: &mut FilePosition, input: &[u8], other: &'e [u8]) -> bool {
     if input[cursor.byte..].starts_with(other) {
         cursor.advance(other.len());
@@ -68,22 +92,27 @@ pub fn check_exact_bytes<'e>(cursor: &mut FilePosition, input: &[u8], other: &'e
     false
 }
 
+/// Checks if the cursor is at the end of input. Never moves the cursor.
 pub fn check_eof(cursor: &mut FilePosition, input: &[u8]) -> bool {
     cursor.byte >= input.len()
 }
 
+/// The way the text returned by `expect_terminated_text` was terminated.
 pub enum TermType {
+    /// The text was terminated by the requested sequence.
     Sequence,
+    /// The text was terminated by the end of line or the end of input.
     EolOrEof,
 }
 
+/// Returns the text until the end of line and moves the cursor to the end of line.
+///
+/// The new line itself is not consumed.
 pub fn expect_text<'a>(cursor: &mut FilePosition, input: &'a [u8]) -> LexResult<Contents<'a>> {
     let start_cursor = cursor.clone();
     let mut end = start_cursor.byte;
     loop {
-        if end >= input.len() || input[end..].starts_with(b"\n")
-            || input[end..].starts_with(b"\r\n")
-        {
+        if end >= input.len() || input[end] == b'\n' || input[end] == b'\r' {
             break;
         }
 
@@ -94,6 +123,10 @@ pub fn expect_text<'a>(cursor: &mut FilePosition, input: &'a [u8]) -> LexResult<
     return Ok(Contents::new(input, start_cursor, *cursor));
 }
 
+/// Returns the text until the `term_sequence` or the end of line, whichever comes first.
+///
+/// If the sequence was found, the cursor is moved past it, and the returned contents do not
+/// include it. Otherwise the cursor is moved to the end of line, and the new line is not consumed.
 pub fn expect_terminated_text<'a, 'e>(
     cursor: &mut FilePosition,
     input: &'a [u8],
@@ -102,9 +135,7 @@ pub fn expect_terminated_text<'a, 'e>(
     let start_cursor = cursor.clone();
     let mut end = start_cursor.byte;
     loop {
-        if end >= input.len() || input[end..].starts_with(b"\n")
-            || input[end..].starts_with(b"\r\n")
-        {
+        if end >= input.len() || input[end] == b'\n' || input[end] == b'\r' {
             break;
         }
         if input[end..].starts_with(term_sequence) {
@@ -126,6 +157,35 @@ pub fn expect_terminated_text<'a, 'e>(
     ));
 }
 
+/// Returns the text until any of the `term_sequences` or the end of line, whichever comes first.
+///
+/// If a sequence was found, returns its index in `term_sequences` and moves the cursor past it.
+/// The returned contents do not include it. Sequences are checked in order at every byte.
+/// Otherwise the cursor is moved to the end of line, and the new line is not consumed.
+pub fn expect_text_terminated_by_any<'a>(
+    cursor: &mut FilePosition,
+    input: &'a [u8],
+    term_sequences: &[&[u8]],
+) -> LexResult<(Contents<'a>, Option<usize>)> {
+    let start_cursor = *cursor;
+    let mut end = start_cursor.byte;
+    loop {
+        if end >= input.len() || input[end] == b'\n' || input[end] == b'\r' {
+            break;
+        }
+        if let Some(i) = term_sequences.iter().position(|t| input[end..].starts_with(t)) {
+            let end_cursor = cursor.advanced(end - start_cursor.byte);
+            cursor.advance(end - start_cursor.byte + term_sequences[i].len());
+            return Ok((Contents::new(input, start_cursor, end_cursor), Some(i)));
+        }
+
+        end += 1;
+    }
+
+    cursor.advance(end - start_cursor.byte);
+    Ok((Contents::new(input, start_cursor, *cursor), None))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -5,16 +5,20 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-mod combinator;
+pub mod combinator;
 
-use error::{At, FilePosition, LexError, LexResult};
+use error::{At, FilePosition, LexError, LexResult, OptionsError};
 use spec;
 use std::collections::VecDeque;
 use std::fmt;
+use std::iter::FusedIterator;
+use std::result;
 use std::str;
 
+/// Lexer token with its position in the input.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct TokenRef<'a> {
+    /// Token value.
     pub value: TokenValueRef<'a>,
     /// The low position at which this token exists.
     pub lo: FilePosition,
@@ -22,11 +26,35 @@ pub struct TokenRef<'a> {
     pub hi: FilePosition,
 }
 
+/// Lexer token with its position in the input, that owns its value.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Token {
+    /// Token value.
+    pub value: TokenValue,
+    /// The low position at which this token exists.
+    pub lo: FilePosition,
+    /// One byte beyond the last character at which token ends.
+    pub hi: FilePosition,
+}
+
+impl<'a> From<TokenRef<'a>> for Token {
+    fn from(other: TokenRef<'a>) -> Self {
+        Token {
+            value: other.value.into(),
+            lo: other.lo,
+            hi: other.hi,
+        }
+    }
+}
+
+/// Lexer token value that borrows from the input.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum TokenValueRef<'a> {
     Key(&'a str),
     Value(&'a str),
     MatchAnyNumberOfLines,
+    MatchLinesWithin(usize),
+    CaptureLines(&'a str),
     MatchNewline,
     MatchText(&'a str),
     Var(&'a str),
@@ -38,6 +66,8 @@ pub enum TokenValue {
     Key(String),
     Value(String),
     MatchAnyNumberOfLines,
+    MatchLinesWithin(usize),
+    CaptureLines(String),
     MatchNewline,
     MatchText(String),
     Var(String),
@@ -49,6 +79,8 @@ impl<'a> From<TokenValueRef<'a>> for TokenValue {
             TokenValueRef::Key(s) => TokenValue::Key(s.into()),
             TokenValueRef::Value(s) => TokenValue::Value(s.into()),
             TokenValueRef::MatchAnyNumberOfLines => TokenValue::MatchAnyNumberOfLines,
+            TokenValueRef::MatchLinesWithin(max) => TokenValue::MatchLinesWithin(max),
+            TokenValueRef::CaptureLines(s) => TokenValue::CaptureLines(s.into()),
             TokenValueRef::MatchNewline => TokenValue::MatchNewline,
             TokenValueRef::MatchText(s) => TokenValue::MatchText(s.into()),
             TokenValueRef::Var(s) => TokenValue::Var(s.into()),
@@ -62,6 +94,8 @@ impl fmt::Display for TokenValue {
             TokenValue::Key(_) => "key".fmt(f),
             TokenValue::Value(_) => "value".fmt(f),
             TokenValue::MatchAnyNumberOfLines => "match lines".fmt(f),
+            TokenValue::MatchLinesWithin(_) => "match lines within".fmt(f),
+            TokenValue::CaptureLines(_) => "capture lines".fmt(f),
             TokenValue::MatchNewline => "match new line".fmt(f),
             TokenValue::MatchText(_) => "match text".fmt(f),
             TokenValue::Var(_) => "variable".fmt(f),
@@ -75,6 +109,15 @@ pub struct Options<'a> {
     pub marker: &'a [u8],
     pub var_start: &'a [u8],
     pub var_end: &'a [u8],
+    pub param_separator: &'a [u8],
+    pub escape: Option<&'a [u8]>,
+    pub allow_indented_markers: bool,
+}
+
+impl Default for Options<'static> {
+    fn default() -> Options<'static> {
+        spec::Options::default().into()
+    }
 }
 
 impl<'a> From<spec::Options<'a>> for Options<'a> {
@@ -84,6 +127,9 @@ impl<'a> From<spec::Options<'a>> for Options<'a> {
             marker: other.marker.as_bytes(),
             var_start: other.var_start.as_bytes(),
             var_end: other.var_end.as_bytes(),
+            param_separator: other.param_separator.as_bytes(),
+            escape: other.escape.map(|e| e.as_bytes()),
+            allow_indented_markers: other.allow_indented_markers,
         }
     }
 }
@@ -129,10 +175,138 @@ impl<'a> Iter<'a> {
         });
     }
 
+    fn text_token(&mut self, contents: combinator::Contents<'a>) -> LexResult<()> {
+        if !contents.slice.is_empty() {
+            self.token(
+                TokenValueRef::MatchText(str::from_utf8(contents.slice)
+                    .map_err(|e| LexError::from(e).at(contents.lo, contents.hi))?),
+                contents.lo,
+                contents.hi,
+            );
+        }
+        Ok(())
+    }
+
+    /// Adds the sequence after an escape as literal text, or the escape itself if nothing
+    /// that needs escaping follows it.
+    fn escaped_text(&mut self, escape_lo: FilePosition) -> LexResult<()> {
+        let escape = self.options.escape.expect("expected escape option");
+        let lo = self.cursor;
+        let escaped = [
+            escape,
+            self.options.var_start,
+            self.options.marker,
+            self.options.skip_lines,
+        ];
+        for sequence in &escaped {
+            if combinator::check_exact_bytes(&mut self.cursor, self.input, sequence) {
+                let contents = combinator::Contents::new(self.input, lo, self.cursor);
+                return self.text_token(contents);
+            }
+        }
+
+        self.text_token(combinator::Contents::new(self.input, escape_lo, lo))
+    }
+
+    /// Checks if the line starts with a var that begins with `skip_lines`, such as `${..name}`,
+    /// and returns the token that captures lines into the var name.
+    fn check_capture_lines(
+        &mut self,
+    ) -> LexResult<Option<(TokenValueRef<'a>, FilePosition, FilePosition)>> {
+        let start = self.cursor;
+        if !combinator::check_exact_bytes(&mut self.cursor, self.input, self.options.var_start)
+            || !combinator::check_exact_bytes(&mut self.cursor, self.input, self.options.skip_lines)
+        {
+            self.cursor = start;
+            return Ok(None);
+        }
+
+        let (contents, termination) = combinator::expect_terminated_text(
+            &mut self.cursor,
+            self.input,
+            self.options.var_end,
+        )?;
+        if let combinator::TermType::EolOrEof = termination {
+            return Err(LexError::ExpectedSequenceFoundNewline {
+                expected: self.options.var_end.into(),
+            }.at(self.cursor, self.cursor));
+        }
+
+        let name = contents.trimmed();
+        Ok(Some((
+            TokenValueRef::CaptureLines(str::from_utf8(name.slice)
+                .map_err(|e| LexError::from(e).at(name.lo, name.hi))?),
+            name.lo,
+            name.hi,
+        )))
+    }
+
+    /// Checks if `skip_lines` is followed by `^` and the maximum number of lines to skip, such
+    /// as `..^10`, and returns that number.
+    fn check_line_count(&mut self) -> LexResult<Option<usize>> {
+        if !combinator::check_exact_bytes(&mut self.cursor, self.input, b"^") {
+            return Ok(None);
+        }
+
+        let lo = self.cursor;
+        let digits = self.input[lo.byte..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        self.cursor = lo.advanced(digits);
+        str::from_utf8(&self.input[lo.byte..self.cursor.byte])
+            .expect("expected ascii digits")
+            .parse()
+            .map(Some)
+            .map_err(|_| LexError::ExpectedLineCount.at(lo, self.cursor))
+    }
+
+    /// Returns the cursor moved past leading spaces and tabs if indented markers are allowed.
+    fn indented_cursor(&self) -> FilePosition {
+        if !self.options.allow_indented_markers {
+            return self.cursor;
+        }
+        let indent = self.input[self.cursor.byte..]
+            .iter()
+            .take_while(|&&b| b == b' ' || b == b'\t')
+            .count();
+        self.cursor.advanced(indent)
+    }
+
+    /// Checks if the next line continues the param value, and moves past its marker and
+    /// separator if it does.
+    ///
+    /// A continuation line has the marker, optional spaces or tabs, and the separator, with no
+    /// key, such as `##: more`. Its value is lexed as another `Value` token.
+    fn check_value_continuation(&mut self) -> bool {
+        let start = self.cursor;
+        if combinator::check_new_line(&mut self.cursor, self.input) {
+            self.cursor = self.indented_cursor();
+            if combinator::check_exact_bytes(&mut self.cursor, self.input, self.options.marker) {
+                let spaces = self.input[self.cursor.byte..]
+                    .iter()
+                    .take_while(|&&b| b == b' ' || b == b'\t')
+                    .count();
+                self.cursor = self.cursor.advanced(spaces);
+                if combinator::check_exact_bytes(
+                    &mut self.cursor,
+                    self.input,
+                    self.options.param_separator,
+                ) {
+                    return true;
+                }
+            }
+        }
+        self.cursor = start;
+        false
+    }
+
     fn eat_bytes(&mut self, mut state: LexState) -> LexResult<LexState> {
         while self.tokens.is_empty() {
             state = match state {
                 LexState::LineStart { content_line_end } => {
+                    let start = self.cursor;
+                    self.cursor = self.indented_cursor();
                     if combinator::check_exact_bytes(
                         &mut self.cursor,
                         self.input,
@@ -140,14 +314,18 @@ impl<'a> Iter<'a> {
                     ) {
                         LexState::ParamKey
                     } else {
+                        self.cursor = start;
                         LexState::ContentStart {
                             content_line_end: content_line_end,
                         }
                     }
                 }
                 LexState::ParamKey => {
-                    let (contents, termination) =
-                        combinator::expect_terminated_text(&mut self.cursor, self.input, b":")?;
+                    let (contents, termination) = combinator::expect_terminated_text(
+                        &mut self.cursor,
+                        self.input,
+                        self.options.param_separator,
+                    )?;
                     let trimmed = contents.trimmed();
                     self.token(
                         TokenValueRef::Key(str::from_utf8(trimmed.slice)
@@ -161,30 +339,53 @@ impl<'a> Iter<'a> {
                     }
                 }
                 LexState::ParamValue => {
-                    let name = combinator::expect_text(&mut self.cursor, self.input)?.trimmed();
+                    let mut name = combinator::expect_text(&mut self.cursor, self.input)?.trimmed();
+                    if name.slice.starts_with(b"\"") {
+                        if name.slice.len() < 2 || !name.slice.ends_with(b"\"") {
+                            return Err(LexError::UnterminatedQuote.at(name.lo, name.hi));
+                        }
+                        name = name.stripped(1);
+                    }
                     self.token(
                         TokenValueRef::Value(str::from_utf8(name.slice)
                             .map_err(|e| LexError::from(e).at(name.lo, name.hi))?),
                         name.lo,
                         name.hi,
                     );
-                    LexState::Eol
+                    if self.check_value_continuation() {
+                        LexState::ParamValue
+                    } else {
+                        LexState::Eol
+                    }
                 }
                 LexState::ContentStart { content_line_end } => {
-                    if combinator::check_exact_bytes(
+                    let start = self.cursor;
+                    self.cursor = self.indented_cursor();
+                    let skip_token = if combinator::check_exact_bytes(
                         &mut self.cursor,
                         self.input,
                         self.options.skip_lines,
                     ) {
-                        let pos = self.cursor.clone();
+                        let token = match self.check_line_count()? {
+                            Some(max) => TokenValueRef::MatchLinesWithin(max),
+                            None => TokenValueRef::MatchAnyNumberOfLines,
+                        };
+                        Some((token, self.cursor, self.cursor))
+                    } else {
+                        self.check_capture_lines()?
+                    };
+                    if skip_token.is_none() {
+                        self.cursor = start;
+                    }
+                    if let Some((token, lo, hi)) = skip_token {
                         if combinator::check_new_line(&mut self.cursor, self.input) {
-                            self.token(TokenValueRef::MatchAnyNumberOfLines, pos, pos);
+                            self.token(token, lo, hi);
                             LexState::LineStart {
                                 content_line_end: None,
                             }
                         } else {
                             if self.cursor.byte == self.input.len() {
-                                self.token(TokenValueRef::MatchAnyNumberOfLines, pos, pos);
+                                self.token(token, lo, hi);
                                 LexState::Eol
                             } else {
                                 return Err(LexError::ExpectedNewline
@@ -229,22 +430,28 @@ impl<'a> Iter<'a> {
                     }
                 }
                 LexState::ContentContinued => {
-                    let (contents, termination) = combinator::expect_terminated_text(
-                        &mut self.cursor,
-                        self.input,
-                        self.options.var_start,
-                    )?;
-                    if contents.slice.len() > 0 {
-                        self.token(
-                            TokenValueRef::MatchText(str::from_utf8(contents.slice)
-                                .map_err(|e| LexError::from(e).at(contents.lo, contents.hi))?),
-                            contents.lo,
-                            contents.hi,
-                        );
-                    }
+                    let var_start = self.options.var_start;
+                    let (contents, termination) = match self.options.escape {
+                        Some(escape) => combinator::expect_text_terminated_by_any(
+                            &mut self.cursor,
+                            self.input,
+                            &[var_start, escape],
+                        )?,
+                        None => combinator::expect_text_terminated_by_any(
+                            &mut self.cursor,
+                            self.input,
+                            &[var_start],
+                        )?,
+                    };
+                    let escape_lo = contents.hi;
+                    self.text_token(contents)?;
                     match termination {
-                        combinator::TermType::EolOrEof => LexState::ContentEol,
-                        combinator::TermType::Sequence => LexState::Var,
+                        None => LexState::ContentEol,
+                        Some(0) => LexState::Var,
+                        Some(_) => {
+                            self.escaped_text(escape_lo)?;
+                            LexState::ContentContinued
+                        }
                     }
                 }
                 LexState::ContentEol => {
@@ -311,6 +518,39 @@ impl<'a> Iterator for Iter<'a> {
     }
 }
 
+impl<'a> FusedIterator for Iter<'a> {}
+
+/// Iterator over owned lexer tokens, returned by `lex`.
+///
+/// Stops after the first error.
+pub struct TokenIter<'a> {
+    inner: Iter<'a>,
+}
+
+impl<'a> Iterator for TokenIter<'a> {
+    type Item = LexResult<Token>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|r| r.map(Token::from))
+    }
+}
+
+impl<'a> FusedIterator for TokenIter<'a> {}
+
+/// Splits specification contents into tokens, for tools that need more than the parsed AST.
+///
+/// Returns an error if options can not be used to tokenize a specification.
+pub fn lex<'a>(
+    options: spec::Options<'a>,
+    input: &'a [u8],
+) -> result::Result<TokenIter<'a>, OptionsError> {
+    options.validate()?;
+
+    Ok(TokenIter {
+        inner: tokenize(options.into(), input),
+    })
+}
+
 pub fn tokenize<'a>(options: Options<'a>, input: &'a [u8]) -> Iter<'a> {
     Iter {
         options: options,
@@ -318,7 +558,7 @@ pub fn tokenize<'a>(options: Options<'a>, input: &'a [u8]) -> Iter<'a> {
             content_line_end: None,
         }),
         tokens: VecDeque::new(),
-        cursor: FilePosition::new(),
+        cursor: FilePosition::start_of(input),
         input: input,
     }
 }
@@ -327,15 +567,6 @@ pub fn tokenize<'a>(options: Options<'a>, input: &'a [u8]) -> Iter<'a> {
 mod tests {
     use super::*;
 
-    fn default_options() -> Options<'static> {
-        Options {
-            skip_lines: b"..",
-            marker: b"##",
-            var_start: b"${",
-            var_end: b"}",
-        }
-    }
-
     pub fn expect_next<'a, 'r>(iter: &'r mut Iter<'a>) -> TokenValueRef<'a> {
         match iter.next() {
             Some(Ok(TokenRef { value, .. })) => value,
@@ -343,18 +574,123 @@ mod tests {
         }
     }
 
+    fn escape_options() -> Options<'static> {
+        Options {
+            escape: Some(b"\\"),
+            ..Options::default()
+        }
+    }
+
+    #[test]
+    fn test_escaped_marker_at_line_start() {
+        let mut tokens = tokenize(escape_options(), b"\\## lib: hello");
+
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText("##"));
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText(" lib: hello"));
+        assert_eq!(tokens.next(), None);
+    }
+
+    #[test]
+    fn test_escaped_skip_lines_at_line_start() {
+        let mut tokens = tokenize(escape_options(), b"a\n\\..");
+
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText("a"));
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchNewline);
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText(".."));
+        assert_eq!(tokens.next(), None);
+    }
+
+    #[test]
+    fn test_escaped_var_start_and_escape() {
+        let mut tokens = tokenize(escape_options(), b"a\\${b} \\\\${c}\\n");
+
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText("a"));
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText("${"));
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText("b} "));
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText("\\"));
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::Var("c"));
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText("\\"));
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText("n"));
+        assert_eq!(tokens.next(), None);
+    }
+
+    #[test]
+    fn test_indented_marker_and_skip_lines() {
+        let options = Options {
+            allow_indented_markers: true,
+            ..Options::default()
+        };
+        let mut tokens = tokenize(options, b"  ## a: b\n\t..\n  c");
+
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::Key("a"));
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::Value("b"));
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchAnyNumberOfLines);
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText("  c"));
+        assert_eq!(tokens.next(), None);
+    }
+
+    #[test]
+    fn test_indented_marker_is_content_by_default() {
+        let mut tokens = tokenize(Options::default(), b"  ## a: b");
+
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText("  ## a: b"));
+        assert_eq!(tokens.next(), None);
+    }
+
+    #[test]
+    fn test_capture_lines() {
+        let mut tokens = tokenize(Options::default(), b"a\n${..body}\nb");
+
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText("a"));
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::CaptureLines("body"));
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText("b"));
+        assert_eq!(tokens.next(), None);
+    }
+
+    #[test]
+    fn test_escape_is_literal_without_option() {
+        let mut tokens = tokenize(Options::default(), b"\\${a}");
+
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText("\\"));
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::Var("a"));
+        assert_eq!(tokens.next(), None);
+    }
+
     #[test]
     fn test_single_param_line() {
-        let mut tokens = tokenize(default_options(), b"## lib: hello");
+        let mut tokens = tokenize(Options::default(), b"## lib: hello");
 
         assert_eq!(expect_next(&mut tokens), TokenValueRef::Key("lib"));
         assert_eq!(expect_next(&mut tokens), TokenValueRef::Value("hello"));
         assert_eq!(tokens.next(), None);
     }
 
+    #[test]
+    fn test_quoted_param_value() {
+        let mut tokens = tokenize(Options::default(), b"## file: \"  spaced: path.txt  \" ");
+
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::Key("file"));
+        assert_eq!(
+            expect_next(&mut tokens),
+            TokenValueRef::Value("  spaced: path.txt  ")
+        );
+        assert_eq!(tokens.next(), None);
+    }
+
+    #[test]
+    fn test_unterminated_quoted_param_value() {
+        let mut tokens = tokenize(Options::default(), b"## file: \"path.txt");
+
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::Key("file"));
+        match tokens.next() {
+            Some(Err(At { desc: LexError::UnterminatedQuote, .. })) => (),
+            o => panic!("expected unterminated quote error but got {:?}", o),
+        }
+    }
+
     #[test]
     fn test_single_content_line() {
-        let mut tokens = tokenize(default_options(), b"Blah blah blah");
+        let mut tokens = tokenize(Options::default(), b"Blah blah blah");
 
         assert_eq!(
             expect_next(&mut tokens),
@@ -367,7 +703,7 @@ mod tests {
     fn test_single_line_with_var() {
         let mut tokens;
 
-        tokens = tokenize(default_options(), b"${ haha, yay }");
+        tokens = tokenize(Options::default(), b"${ haha, yay }");
         assert_eq!(expect_next(&mut tokens), TokenValueRef::Var("haha, yay"));
         assert_eq!(tokens.next(), None);
     }
@@ -376,7 +712,7 @@ mod tests {
     fn test_single_line_with_content_and_var() {
         let mut tokens;
 
-        tokens = tokenize(default_options(), b"Foo ${ haha, yay }");
+        tokens = tokenize(Options::default(), b"Foo ${ haha, yay }");
         assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText("Foo "));
         assert_eq!(expect_next(&mut tokens), TokenValueRef::Var("haha, yay"));
         assert_eq!(tokens.next(), None);
@@ -386,7 +722,7 @@ mod tests {
     fn test_single_line_with_var_and_content() {
         let mut tokens;
 
-        tokens = tokenize(default_options(), b"${ haha, yay } Bar");
+        tokens = tokenize(Options::default(), b"${ haha, yay } Bar");
         assert_eq!(expect_next(&mut tokens), TokenValueRef::Var("haha, yay"));
         assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText(" Bar"));
         assert_eq!(tokens.next(), None);
@@ -396,20 +732,20 @@ mod tests {
     fn test_single_line_mixed() {
         let mut tokens;
 
-        tokens = tokenize(default_options(), b"Foo ${ haha, yay } Bar");
+        tokens = tokenize(Options::default(), b"Foo ${ haha, yay } Bar");
         assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText("Foo "));
         assert_eq!(expect_next(&mut tokens), TokenValueRef::Var("haha, yay"));
         assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText(" Bar"));
         assert_eq!(tokens.next(), None);
 
-        tokens = tokenize(default_options(), b"Foo ${zai} Bar${x}");
+        tokens = tokenize(Options::default(), b"Foo ${zai} Bar${x}");
         assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText("Foo "));
         assert_eq!(expect_next(&mut tokens), TokenValueRef::Var("zai"));
         assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText(" Bar"));
         assert_eq!(expect_next(&mut tokens), TokenValueRef::Var("x"));
         assert_eq!(tokens.next(), None);
 
-        tokens = tokenize(default_options(), b"Foo ${}");
+        tokens = tokenize(Options::default(), b"Foo ${}");
         assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText("Foo "));
         assert_eq!(expect_next(&mut tokens), TokenValueRef::Var(""));
         assert_eq!(tokens.next(), None);
@@ -420,7 +756,7 @@ mod tests {
         let mut tokens;
 
         tokens = tokenize(
-            default_options(),
+            Options::default(),
             b"## lib: hello
 ${ X }",
         );
@@ -435,7 +771,7 @@ ${ X }",
         let mut tokens;
 
         tokens = tokenize(
-            default_options(),
+            Options::default(),
             b"## lib: hello
 ..
 ${ X }
@@ -455,7 +791,7 @@ ${ X }
         assert_eq!(tokens.next(), None);
 
         tokens = tokenize(
-            default_options(),
+            Options::default(),
             b"## lib: hello
 ${ X }
 ..
@@ -476,7 +812,7 @@ ${ X }
         let mut tokens;
 
         tokens = tokenize(
-            default_options(),
+            Options::default(),
             b"..
 ${ X }
 ..
@@ -505,7 +841,7 @@ ${ X }
         let mut tokens;
 
         tokens = tokenize(
-            default_options(),
+            Options::default(),
             b"## a: b
 f ${ X } b
 ..
@@ -540,7 +876,7 @@ k ${ Y } z
         let mut tokens;
 
         tokens = tokenize(
-            default_options(),
+            Options::default(),
             b"..
 a
 b
@@ -560,4 +896,21 @@ b
         );
         assert_eq!(tokens.next(), None);
     }
+
+    #[test]
+    fn test_cr_line_endings() {
+        let mut tokens = tokenize(Options::default(), b"## file: a\r..\ra\rb${x}\r");
+
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::Key("file"));
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::Value("a"));
+        assert_eq!(
+            expect_next(&mut tokens),
+            TokenValueRef::MatchAnyNumberOfLines
+        );
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText("a"));
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchNewline);
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::MatchText("b"));
+        assert_eq!(expect_next(&mut tokens), TokenValueRef::Var("x"));
+        assert_eq!(tokens.next(), None);
+    }
 }
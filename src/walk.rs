@@ -5,71 +5,191 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use Result;
+use {Error, Result};
 use spec::{Options, Spec};
+use spec_path::{has_extension, SpecPath};
 use std::fs::File;
 use std::io::Read;
+use std::iter::FusedIterator;
 use std::path::{Path, PathBuf};
 use walkdir::{self, WalkDir};
 
-/// Parsed specification at a path.
-#[derive(Debug, Clone)]
-pub struct SpecPath {
-    pub spec: Spec,
-    pub path: PathBuf,
-}
+/// Predicate that decides which directory entries are walked.
+type EntryFilter<'a> = Box<dyn FnMut(&walkdir::DirEntry) -> bool + Send + 'a>;
 
 /// Iterator over parsed specification files.
+///
+/// It is `Send`, so the walk can be moved to another thread. To move it into a spawned
+/// thread, create it with `'static` options, such as string literals.
 pub struct SpecWalkIter<'a> {
-    extension: &'a str,
-    walk_dir: walkdir::IntoIter,
+    extensions: Vec<&'a str>,
+    /// Walk configuration, until the walk is started by the first `next` call.
+    pending: Option<(WalkDir, EntryFilter<'a>)>,
+    walk_dir: Option<walkdir::FilterEntry<walkdir::IntoIter, EntryFilter<'a>>>,
     options: Options<'a>,
+    strict: bool,
 }
 
 impl<'a> Iterator for SpecWalkIter<'a> {
     type Item = Result<SpecPath>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some((walk_dir, filter)) = self.pending.take() {
+            self.walk_dir = Some(walk_dir.into_iter().filter_entry(filter));
+        }
         loop {
-            match self.walk_dir.next() {
+            match self.walk_dir.as_mut()?.next() {
                 None => return None,
                 Some(Err(e)) => return Some(Err(e.into())),
                 Some(Ok(entry)) => {
-                    return Some(
-                        match (entry.file_type().is_file(), entry.path().extension()) {
-                            (true, Some(v)) if v == self.extension => self.process_entry(&entry),
-                            _ => continue,
-                        },
-                    )
+                    if !entry.file_type().is_file() || !self.has_extension(entry.path()) {
+                        continue;
+                    }
+                    return Some(self.process_entry(&entry));
                 }
             }
         }
     }
 }
 
+impl<'a> FusedIterator for SpecWalkIter<'a> {}
+
 impl<'a> SpecWalkIter<'a> {
+    /// Returns specification files sorted by path.
+    ///
+    /// Entries of every directory are sorted by file name, so the order does not depend on the
+    /// file system.
+    pub fn sorted(self) -> SpecWalkIter<'a> {
+        self.configure(|walk_dir| walk_dir.sort_by(|a, b| a.file_name().cmp(b.file_name())))
+    }
+
+    /// Ignores everything nested deeper than `depth` levels below the walked directory.
+    ///
+    /// The walked directory is at depth 0, and files directly in it are at depth 1. Deeper
+    /// directories are never read, and are not reported as errors.
+    pub fn max_depth(self, depth: usize) -> SpecWalkIter<'a> {
+        self.configure(|walk_dir| walk_dir.max_depth(depth))
+    }
+
+    /// Follows symbolic links if `follow_links` is true.
+    ///
+    /// Specification files found through a link have `SpecPath::path` inside the linked path,
+    /// not the path of the link target. A link to one of its own parent directories would make
+    /// the walk endless, so it is returned as an `Error::WalkDir` instead of being followed.
+    pub fn follow_links(self, follow_links: bool) -> SpecWalkIter<'a> {
+        self.configure(|walk_dir| walk_dir.follow_links(follow_links))
+    }
+
+    /// Parses specification files with `Spec::parse_strict` instead of `Spec::parse`.
+    pub fn strict(mut self) -> SpecWalkIter<'a> {
+        self.strict = true;
+        self
+    }
+
+    /// Changes the walk configuration if the walk has not started yet.
+    fn configure<F: FnOnce(WalkDir) -> WalkDir>(mut self, f: F) -> SpecWalkIter<'a> {
+        self.pending = self.pending.map(|(walk_dir, filter)| (f(walk_dir), filter));
+        self
+    }
+
+    fn has_extension(&self, path: &Path) -> bool {
+        has_extension(path, &self.extensions)
+    }
+
     fn process_entry(&mut self, entry: &walkdir::DirEntry) -> Result<SpecPath> {
         let path: PathBuf = entry.path().into();
         let mut contents = String::new();
         File::open(&path)?.read_to_string(&mut contents)?;
-        Spec::parse(self.options, contents.as_bytes())
-            .map(|spec| SpecPath {
-                spec: spec,
-                path: (&path).clone(),
-            })
-            .map_err(move |e| (path, e).into())
+        let spec = if self.strict {
+            Spec::parse_strict(self.options, contents.as_bytes())
+        } else {
+            Spec::parse(self.options, contents.as_bytes())
+        };
+        spec.map(|spec| SpecPath {
+            spec: spec,
+            path: (&path).clone(),
+        }).map_err(move |e| (path, e).into())
     }
 }
 
 /// Walks spec directory and returns the iterator over all parsed `SpecPath` objects.
-pub fn walk_spec_dir<'a>(
+///
+/// Options can be passed either as `Options` or as a reference to `OwnedOptions`.
+pub fn walk_spec_dir<'a, O: Into<Options<'a>>>(
     path: &Path,
     extension: &'a str,
-    options: Options<'a>,
+    options: O,
 ) -> SpecWalkIter<'a> {
+    walk_spec_dir_ext(path, &[extension], options)
+}
+
+/// Walks spec directory and returns the iterator over parsed `SpecPath` objects of files
+/// with any of the `extensions`.
+///
+/// Extensions are compared ignoring ASCII case.
+pub fn walk_spec_dir_ext<'a, O: Into<Options<'a>>>(
+    path: &Path,
+    extensions: &[&'a str],
+    options: O,
+) -> SpecWalkIter<'a> {
+    walk_spec_dir_with(WalkDir::new(path), extensions, options, |_| true)
+}
+
+/// Walks spec directory and returns the iterator over parsed `SpecPath` objects of files
+/// for which `filter` returns true.
+///
+/// Directories for which `filter` returns false are not descended into, so files in them
+/// are never read. The walked directory itself is filtered too. The `filter` must be `Send`
+/// to keep the iterator `Send`.
+pub fn walk_spec_dir_filtered<'a, O, F>(
+    path: &Path,
+    extension: &'a str,
+    options: O,
+    filter: F,
+) -> SpecWalkIter<'a>
+where
+    O: Into<Options<'a>>,
+    F: Fn(&Path) -> bool + Send + 'a,
+{
+    walk_spec_dir_with(WalkDir::new(path), &[extension], options, filter)
+}
+
+fn walk_spec_dir_with<'a, O, F>(
+    walk_dir: WalkDir,
+    extensions: &[&'a str],
+    options: O,
+    filter: F,
+) -> SpecWalkIter<'a>
+where
+    O: Into<Options<'a>>,
+    F: Fn(&Path) -> bool + Send + 'a,
+{
+    let filter: EntryFilter<'a> = Box::new(move |entry: &walkdir::DirEntry| filter(entry.path()));
     SpecWalkIter {
-        extension: extension,
-        walk_dir: WalkDir::new(path).into_iter(),
-        options: options,
+        extensions: extensions.to_vec(),
+        pending: Some((walk_dir, filter)),
+        walk_dir: None,
+        options: options.into(),
+        strict: false,
+    }
+}
+
+/// Walks spec directory and returns all parsed `SpecPath` objects together with all errors.
+///
+/// Unlike `walk_spec_dir`, this does not stop at the first broken file, so every error can
+/// be reported at once.
+pub fn walk_spec_dir_collect<'a, O: Into<Options<'a>>>(
+    path: &Path,
+    extension: &'a str,
+    options: O,
+) -> (Vec<SpecPath>, Vec<Error>) {
+    let mut specs = Vec::new();
+    let mut errors = Vec::new();
+    for maybe_spec in walk_spec_dir(path, extension, options) {
+        match maybe_spec {
+            Ok(spec) => specs.push(spec),
+            Err(e) => errors.push(e),
+        }
     }
+    (specs, errors)
 }
@@ -0,0 +1,84 @@
+extern crate specker;
+
+mod support;
+
+#[cfg(test)]
+mod anchored {
+    use specker::{At, Match, MatchOptions, TemplateMatchError};
+    use std::collections::HashMap;
+    use support::new_item;
+
+    fn match_unanchored(template: &[Match], contents: &str) -> Result<(), At<TemplateMatchError>> {
+        let options = MatchOptions {
+            anchored: false,
+            ..MatchOptions::default()
+        };
+        let mut cursor = ::std::io::Cursor::new(contents.as_bytes());
+        new_item(template).match_contents_with(&mut cursor, &HashMap::new(), &options)
+    }
+
+    fn two_lines() -> Vec<Match> {
+        vec![
+            Match::Text("a".into()),
+            Match::NewLine,
+            Match::Text("b".into()),
+        ]
+    }
+
+    #[test]
+    fn anchored_by_default() {
+        assert!(MatchOptions::default().anchored);
+        new_item(&two_lines())
+            .match_bytes(b"x\na\nb\ny", &HashMap::new())
+            .err()
+            .expect("expected error");
+    }
+
+    #[test]
+    fn template_matches_inside_input() {
+        match_unanchored(&two_lines(), "x\na\nb\ny").expect("expected match");
+        match_unanchored(&two_lines(), "a\nb").expect("expected match");
+        match_unanchored(&two_lines(), "x\ny\na\nb\n").expect("expected match");
+    }
+
+    #[test]
+    fn template_with_final_new_line_matches_inside_input() {
+        let template = &[Match::Text("a".into()), Match::NewLine];
+        match_unanchored(template, "x\na\ny").expect("expected match");
+    }
+
+    #[test]
+    fn lines_must_match_whole_input_lines() {
+        match_unanchored(&two_lines(), "xa\nb")
+            .err()
+            .expect("expected error");
+        match_unanchored(&two_lines(), "a\nbx")
+            .err()
+            .expect("expected error");
+    }
+
+    #[test]
+    fn missing_template_is_reported() {
+        let err = match_unanchored(&two_lines(), "x\na\ny")
+            .err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::ExpectedText {
+                expected: "b".into(),
+                found: "y".into(),
+                found_line: "y".into(),
+            },
+            (2, 0),
+            (2, 1),
+        ).unwrap();
+    }
+
+    #[test]
+    fn explicit_skips_are_redundant() {
+        let mut template = vec![Match::MultipleLines];
+        template.extend(two_lines());
+        template.push(Match::MultipleLines);
+        match_unanchored(&template, "x\na\nb\ny").expect("expected match");
+        match_unanchored(&template, "a\nb").expect("expected match");
+    }
+}
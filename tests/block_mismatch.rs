@@ -0,0 +1,152 @@
+extern crate specker;
+
+mod support;
+
+#[cfg(test)]
+mod block_mismatch {
+    use specker::{At, Match, MatchOptions, TemplateMatchError};
+    use std::collections::HashMap;
+    use support::new_item;
+
+    fn match_blocks(
+        template: &[Match],
+        contents: &str,
+        params: &HashMap<&str, &str>,
+    ) -> Result<(), At<TemplateMatchError>> {
+        let options = MatchOptions {
+            report_block_mismatch: true,
+            ..MatchOptions::default()
+        };
+        let mut cursor = ::std::io::Cursor::new(contents.as_bytes());
+        new_item(template).match_contents_with(&mut cursor, params, &options)
+    }
+
+    fn block() -> Vec<Match> {
+        vec![
+            Match::Text("fn main() {".into()),
+            Match::NewLine,
+            Match::Text("    ".into()),
+            Match::Var("body".into()),
+            Match::NewLine,
+            Match::Text("}".into()),
+            Match::NewLine,
+        ]
+    }
+
+    #[test]
+    fn failing_line_reports_whole_block() {
+        let mut params = HashMap::new();
+        params.insert("body", "run();");
+
+        let err = match_blocks(&block(), "fn main() {\n    stop();\n}\n", &params)
+            .err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::BlockMismatch {
+                expected: vec!["fn main() {".into(), "    run();".into(), "}".into()],
+                found: vec!["fn main() {".into(), "    stop();".into(), "}".into()],
+                first_line: 0,
+            },
+            (1, 0),
+            (1, 11),
+        ).unwrap();
+    }
+
+    #[test]
+    fn block_starts_after_skipped_lines() {
+        let template = [
+            Match::Text("a".into()),
+            Match::MultipleLines,
+            Match::Text("b".into()),
+            Match::NewLine,
+            Match::Text("c".into()),
+        ];
+
+        let err = match_blocks(&template, "a\nx\nb\nd", &HashMap::new())
+            .err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::BlockMismatch {
+                expected: vec!["b".into(), "c".into()],
+                found: vec!["b".into(), "d".into()],
+                first_line: 2,
+            },
+            (3, 0),
+            (3, 1),
+        ).unwrap();
+    }
+
+    #[test]
+    fn found_lines_stop_at_end_of_input() {
+        let template = [
+            Match::Text("a".into()),
+            Match::NewLine,
+            Match::Text("b".into()),
+            Match::NewLine,
+            Match::Text("c".into()),
+        ];
+
+        let err = match_blocks(&template, "a\nx", &HashMap::new())
+            .err()
+            .expect("expected error");
+        assert_eq!(
+            err.desc,
+            TemplateMatchError::BlockMismatch {
+                expected: vec!["a".into(), "b".into(), "c".into()],
+                found: vec!["a".into(), "x".into()],
+                first_line: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn single_line_block_reports_expected_text() {
+        let template = [
+            Match::Text("a".into()),
+            Match::MultipleLines,
+            Match::Text("b".into()),
+        ];
+
+        let err = match_blocks(&template, "a\nc", &HashMap::new())
+            .err()
+            .expect("expected error");
+        match err.desc {
+            TemplateMatchError::ExpectedTextFoundEof { .. } => (),
+            other => panic!("unexpected error {:?}", other),
+        }
+
+        let err = match_blocks(&template[..1], "c\n", &HashMap::new())
+            .err()
+            .expect("expected error");
+        match err.desc {
+            TemplateMatchError::ExpectedText { .. } => (),
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_param_is_not_a_block_mismatch() {
+        let err = match_blocks(&block(), "fn main() {\n    run();\n}\n", &HashMap::new())
+            .err()
+            .expect("expected error");
+        assert_eq!(err.desc, TemplateMatchError::MissingParam("body".into()));
+    }
+
+    #[test]
+    fn block_mismatch_is_disabled_by_default() {
+        let template = [
+            Match::Text("a".into()),
+            Match::NewLine,
+            Match::Text("b".into()),
+        ];
+        let mut cursor = ::std::io::Cursor::new(&b"a\nc"[..]);
+        let err = new_item(&template)
+            .match_contents(&mut cursor, &HashMap::new())
+            .err()
+            .expect("expected error");
+        match err.desc {
+            TemplateMatchError::ExpectedText { .. } => (),
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+extern crate specker;
+
+#[cfg(test)]
+mod capture_lines {
+    use specker::{Options, Spec};
+    use std::collections::HashMap;
+
+    fn options() -> Options<'static> {
+        Options {
+            skip_lines: "..",
+            marker: "##",
+            var_start: "${",
+            var_end: "}",
+            param_separator: ":",
+            escape: None,
+            allow_indented_markers: false,
+            case_insensitive_keys: false,
+            interpret_escapes: false,
+        }
+    }
+
+    fn captures(spec: &[u8], contents: &[u8]) -> HashMap<String, String> {
+        let spec = Spec::parse(options(), spec).unwrap();
+        let item = spec.iter().next().expect("expected item");
+        item.match_captures(&mut &contents[..], &HashMap::new())
+            .expect("expected match")
+    }
+
+    #[test]
+    fn captures_skipped_lines() {
+        let captured = captures(
+            b"fn main() {\n${..body}\n}",
+            b"fn main() {\n    a();\n    b();\n}",
+        );
+        assert_eq!(captured["body"], "    a();\n    b();\n");
+    }
+
+    #[test]
+    fn captures_trailing_lines() {
+        let captured = captures(b"start\n${..rest}", b"start\none\ntwo");
+        assert_eq!(captured["rest"], "one\ntwo");
+    }
+
+    #[test]
+    fn captures_nothing_for_multiple_lines() {
+        let captured = captures(b"start\n..\nend", b"start\none\nend");
+        assert!(captured.is_empty());
+    }
+
+    #[test]
+    fn capture_lines_still_match_contents() {
+        let spec = Spec::parse(options(), b"a\n${..body}\nb").unwrap();
+        let item = spec.iter().next().expect("expected item");
+        item.match_contents(&mut &b"a\nx\nb"[..], &HashMap::new())
+            .expect("expected match");
+    }
+}
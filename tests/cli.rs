@@ -0,0 +1,117 @@
+#![cfg(feature = "walk")]
+
+#[cfg(test)]
+mod cli {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::process::{Command, Output};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join("specker-tests").join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn check(spec_dir: &Path, args: &[&str]) -> Output {
+        run("check", spec_dir, args)
+    }
+
+    fn run(command: &str, spec_dir: &Path, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_specker"))
+            .arg(command)
+            .arg("--spec-dir")
+            .arg(spec_dir)
+            .args(args)
+            .output()
+            .unwrap()
+    }
+
+    #[test]
+    fn matching_files_succeed() {
+        let dir = temp_dir("cli_matching_files_succeed");
+        fs::write(dir.join("spec.txt"), "## file: a.out\nhello ${name}").unwrap();
+        fs::write(dir.join("a.out"), "hello world").unwrap();
+
+        let output = check(&dir, &["-D", "name=world"]);
+        assert_eq!(output.status.code(), Some(0));
+    }
+
+    #[test]
+    fn mismatch_is_displayed_and_fails() {
+        let dir = temp_dir("cli_mismatch_is_displayed_and_fails");
+        let base = dir.join("base");
+        fs::create_dir_all(&base).unwrap();
+        fs::write(dir.join("spec.spec"), "<< file: a.out\nhello {{name}}").unwrap();
+        fs::write(base.join("a.out"), "hello there").unwrap();
+
+        let output = check(
+            &dir,
+            &[
+                "--ext",
+                "spec",
+                "--base",
+                base.to_str().unwrap(),
+                "--marker",
+                "<<",
+                "--var-start",
+                "{{",
+                "--var-end",
+                "}}",
+                "-D",
+                "name=world",
+            ],
+        );
+        assert_eq!(output.status.code(), Some(1));
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("Expected \"world\", found \"there\""));
+    }
+
+    #[test]
+    fn invalid_param_is_usage_error() {
+        let output = check(Path::new("."), &["-D", "name"]);
+        assert_eq!(output.status.code(), Some(2));
+    }
+
+    #[test]
+    fn missing_file_is_displayed_and_fails() {
+        let dir = temp_dir("cli_missing_file_is_displayed_and_fails");
+        fs::write(dir.join("spec.txt"), "## file: missing.out\nhello").unwrap();
+
+        let output = check(&dir, &[]);
+        assert_eq!(output.status.code(), Some(1));
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("missing.out"));
+        assert!(stderr.contains("1 failure(s)"));
+    }
+
+    #[test]
+    fn line_range_mismatch_is_displayed_and_fails() {
+        let dir = temp_dir("cli_line_range_mismatch_is_displayed_and_fails");
+        fs::write(dir.join("spec.txt"), "## file: out.txt\n..^1\nfoo\n").unwrap();
+        fs::write(dir.join("out.txt"), "a\nb\nc\nfoo\n").unwrap();
+
+        let output = check(&dir, &[]);
+        assert_eq!(output.status.code(), Some(1));
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("Expected a match within 1 skipped lines"));
+    }
+
+    #[test]
+    fn lint_reports_parse_errors_without_matching() {
+        let dir = temp_dir("cli_lint_reports_parse_errors_without_matching");
+        fs::write(dir.join("good.txt"), "## file: missing.out\nhello").unwrap();
+
+        let output = run("lint", &dir, &[]);
+        assert_eq!(output.status.code(), Some(0));
+
+        fs::write(dir.join("bad.txt"), "## file: a.out\n##\nhello").unwrap();
+
+        let output = run("lint", &dir, &[]);
+        assert_eq!(output.status.code(), Some(1));
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("bad.txt"));
+        assert!(stderr.contains("1 failure(s)"));
+    }
+}
@@ -0,0 +1,93 @@
+extern crate specker;
+
+mod support;
+
+#[cfg(test)]
+mod collapse_blank_lines {
+    use specker::{At, Match, MatchOptions, TemplateMatchError};
+    use std::collections::HashMap;
+    use support::new_item;
+
+    fn match_collapsing(template: &[Match], contents: &str) -> Result<(), At<TemplateMatchError>> {
+        let options = MatchOptions {
+            collapse_blank_lines: true,
+            ..MatchOptions::default()
+        };
+        let mut cursor = ::std::io::Cursor::new(contents.as_bytes());
+        new_item(template).match_contents_with(&mut cursor, &HashMap::new(), &options)
+    }
+
+    fn sections(blank_lines: usize) -> Vec<Match> {
+        let mut template = vec![Match::Text("a".into()), Match::NewLine];
+        for _ in 0..blank_lines {
+            template.push(Match::NewLine);
+        }
+        template.push(Match::Text("b".into()));
+        template
+    }
+
+    #[test]
+    fn many_blank_lines_match_single_blank_line() {
+        match_collapsing(&sections(1), "a\n\n\n\nb").expect("expected match");
+    }
+
+    #[test]
+    fn single_blank_line_matches_many_blank_lines() {
+        match_collapsing(&sections(3), "a\n\nb").expect("expected match");
+    }
+
+    #[test]
+    fn crlf_blank_lines_are_collapsed() {
+        match_collapsing(&sections(1), "a\r\n\r\n\r\nb").expect("expected match");
+    }
+
+    #[test]
+    fn blank_line_is_still_required() {
+        let err = match_collapsing(&sections(1), "a\nb")
+            .err()
+            .expect("expected error");
+        err.assert_matches(&TemplateMatchError::ExpectedEol, (1, 0), (1, 0))
+            .unwrap();
+    }
+
+    #[test]
+    fn blank_lines_are_not_allowed_where_template_has_none() {
+        let err = match_collapsing(&sections(0), "a\n\nb")
+            .err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::ExpectedText {
+                expected: "b".into(),
+                found: "".into(),
+                found_line: "".into(),
+            },
+            (1, 0),
+            (1, 0),
+        ).unwrap();
+    }
+
+    #[test]
+    fn trailing_blank_lines_are_collapsed() {
+        let template = &[Match::Text("a".into()), Match::NewLine, Match::NewLine];
+        match_collapsing(template, "a\n\n").expect("expected match");
+        match_collapsing(template, "a\n\n\n\n").expect("expected match");
+    }
+
+    #[test]
+    fn final_new_line_is_not_collapsed() {
+        let template = &[Match::Text("a".into()), Match::NewLine];
+        match_collapsing(template, "a\n").expect("expected match");
+        match_collapsing(template, "a\n\n")
+            .err()
+            .expect("expected error");
+    }
+
+    #[test]
+    fn blank_lines_are_kept_by_default() {
+        let mut cursor = ::std::io::Cursor::new(&b"a\n\n\nb"[..]);
+        new_item(&sections(1))
+            .match_contents(&mut cursor, &HashMap::new())
+            .err()
+            .expect("expected error");
+    }
+}
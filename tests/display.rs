@@ -0,0 +1,348 @@
+extern crate specker;
+
+#[cfg(test)]
+mod display {
+    use specker::{
+        display_diff, display_error_for_file, display_error_for_read, display_line_mismatch,
+        match_and_display, write_error_for_read, Item, Match,
+    };
+    use std::collections::HashMap;
+    use std::env;
+    use std::fs;
+    use std::io::Cursor;
+    use std::path::{Path, PathBuf};
+
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let dir = env::temp_dir().join("specker-tests").join("display");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn diff_changed_line() {
+        let mut params = HashMap::new();
+        params.insert("name", "world");
+
+        let diff = display_diff(
+            &[
+                Match::Text("hello".into()),
+                Match::NewLine,
+                Match::Text("dear ".into()),
+                Match::Var("name".into()),
+                Match::NewLine,
+                Match::Text("bye".into()),
+            ],
+            &params,
+            "hello\ndear word\nbye",
+        );
+
+        assert_eq!(
+            diff,
+            "--- expected
++++ found
+ hello
+-dear world
++dear word
+ bye
+"
+        );
+    }
+
+    #[test]
+    fn diff_missing_param_and_extra_line() {
+        let diff = display_diff(
+            &[Match::Var("name".into()), Match::MultipleLines],
+            &HashMap::new(),
+            "a\nb",
+        );
+
+        assert_eq!(
+            diff,
+            "--- expected
++++ found
+-${name}
+ a
+ b
+"
+        );
+    }
+
+    #[test]
+    fn diff_lines_taken_by_skip_are_unchanged() {
+        let diff = display_diff(
+            &[
+                Match::Text("a".into()),
+                Match::MultipleLines,
+                Match::Text("b".into()),
+                Match::NewLine,
+                Match::Text("c".into()),
+            ],
+            &HashMap::new(),
+            "a\nx\nb\ny\nc\nz",
+        );
+
+        assert_eq!(
+            diff,
+            "--- expected
++++ found
+ a
+ x
+ b
++y
+ c
++z
+"
+        );
+    }
+
+    fn match_error_snippet(template: &[Match], contents: &str) -> String {
+        let item = Item::new(&[], template);
+        let err = item
+            .match_contents(&mut Cursor::new(contents.as_bytes()), &HashMap::new())
+            .err()
+            .expect("expected error");
+        display_error_for_read(
+            Path::new("contents"),
+            &mut Cursor::new(contents.as_bytes()),
+            &err,
+        )
+    }
+
+    #[test]
+    fn error_snippet() {
+        assert_eq!(
+            match_error_snippet(
+                &[
+                    Match::Text("a".into()),
+                    Match::NewLine,
+                    Match::Text("hi".into()),
+                ],
+                "a\nho",
+            ),
+            "in \"contents\"
+1 | a
+2 | ho
+  | ^^
+  | Expected \"hi\", found \"ho\""
+        );
+    }
+
+    #[test]
+    fn zero_width_error_at_line_start_has_one_caret() {
+        let contents = "a\nb";
+        let template = [Match::Text("a".into())];
+        let err = Item::new(&[], &template)
+            .match_contents(&mut Cursor::new(contents.as_bytes()), &HashMap::new())
+            .err()
+            .expect("expected error");
+        assert_eq!(err.lo, err.hi);
+
+        assert_eq!(
+            display_error_for_read(Path::new("contents"), &mut Cursor::new(contents), &err),
+            "in \"contents\"
+1 | a
+2 | b
+  | ^
+  | Expected end of file"
+        );
+    }
+
+    #[test]
+    fn error_that_ends_before_start_has_one_caret() {
+        let template = [Match::Text("hi".into())];
+        let mut err = Item::new(&[], &template)
+            .match_contents(&mut Cursor::new(&b"ho"[..]), &HashMap::new())
+            .err()
+            .expect("expected error");
+        err.hi.col = 0;
+        err.lo.col = 1;
+
+        let snippet = display_error_for_read(Path::new("contents"), &mut Cursor::new("ho"), &err);
+        assert_eq!(snippet.lines().nth(2), Some("  |  ^"));
+    }
+
+    #[test]
+    fn error_snippet_aligns_line_numbers_of_different_widths() {
+        let mut template = Vec::new();
+        for _ in 0..9 {
+            template.push(Match::Text("a".into()));
+            template.push(Match::NewLine);
+        }
+        template.push(Match::Text("hi".into()));
+
+        assert_eq!(
+            match_error_snippet(&template, &format!("{}ho", "a\n".repeat(9))),
+            "in \"contents\"
+ 8 | a
+ 9 | a
+10 | ho
+   | ^^
+   | Expected \"hi\", found \"ho\""
+        );
+    }
+
+    #[cfg(feature = "fuzzy")]
+    #[test]
+    fn error_snippet_suggests_close_text() {
+        assert_eq!(
+            match_error_snippet(&[Match::Text("<body>".into())], "<bddy>"),
+            "in \"contents\"
+1 | <bddy>
+  | ^^^^^^
+  | Expected \"<body>\", found \"<bddy>\"
+  | did you mean \"<body>\"?
+  |                 ^"
+        );
+    }
+
+    #[cfg(feature = "fuzzy")]
+    #[test]
+    fn error_snippet_does_not_suggest_different_text() {
+        let snippet = match_error_snippet(&[Match::Text("hello".into())], "world");
+        assert!(!snippet.contains("did you mean"));
+    }
+
+    #[test]
+    fn write_error_snippet_is_same_as_display() {
+        let template = [Match::Text("hi".into())];
+        let item = Item::new(&[], &template);
+        let err = item.match_contents(&mut Cursor::new(&b"ho"[..]), &HashMap::new())
+            .err()
+            .expect("expected error");
+
+        let mut output = Vec::new();
+        write_error_for_read(
+            &mut output,
+            Path::new("contents"),
+            &mut Cursor::new(&b"ho"[..]),
+            &err,
+        ).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            match_error_snippet(&template, "ho")
+        );
+    }
+
+    #[test]
+    fn match_and_display_stdin() {
+        let template = [Match::Text("hi".into())];
+        let item = Item::new(&[], &template);
+        let params: HashMap<&str, &str> = HashMap::new();
+
+        match_and_display(&item, &mut Cursor::new(&b"hi"[..]), &params, Path::new("<stdin>"))
+            .expect("expected match");
+
+        let err = match_and_display(
+            &item,
+            &mut Cursor::new(&b"ho"[..]),
+            &params,
+            Path::new("<stdin>"),
+        ).err()
+            .expect("expected error");
+        assert_eq!(
+            err,
+            "in \"<stdin>\"
+1 | ho
+  | ^^
+  | Expected \"hi\", found \"ho\""
+        );
+    }
+
+    #[test]
+    fn long_line_is_windowed_around_error() {
+        let mut contents = "a".repeat(95);
+        contents.push_str(&"c".repeat(51));
+        let snippet = match_error_snippet(
+            &[Match::Text("a".repeat(95)), Match::Text("b".into())],
+            &contents,
+        );
+        let lines: Vec<&str> = snippet.lines().collect();
+
+        assert_eq!(
+            lines[1],
+            format!("1 | …{}{}…", "a".repeat(39), "c".repeat(39))
+        );
+        assert_eq!(lines[2], format!("  | {}{}", " ".repeat(40), "^".repeat(39)));
+        assert!(lines[3].starts_with(&format!("  | {}Expected", " ".repeat(40))));
+    }
+
+    #[test]
+    fn long_line_is_truncated_on_the_right() {
+        let contents = "c".repeat(100);
+        let snippet = match_error_snippet(&[Match::Text("b".into())], &contents);
+        let lines: Vec<&str> = snippet.lines().collect();
+
+        assert_eq!(lines[1], format!("1 | {}…", "c".repeat(78)));
+        assert_eq!(lines[2], format!("  | {}", "^".repeat(78)));
+    }
+
+    #[test]
+    fn line_mismatch_points_at_diverging_token() {
+        let template = [
+            Match::Text("hello".into()),
+            Match::NewLine,
+            Match::Text("dear ".into()),
+            Match::Var("name".into()),
+            Match::Text("!".into()),
+        ];
+        let item = Item::new(&[], &template);
+        let mut params = HashMap::new();
+        params.insert("name", "world");
+        let contents = b"hello\ndear world?";
+
+        let compiled = item.compile();
+        let mismatch = compiled
+            .line_mismatch(contents, &params)
+            .expect("expected mismatch");
+        assert_eq!(mismatch.matched.len(), 2);
+        assert_eq!(mismatch.tokens[mismatch.matched.len()], &Match::Text("!".into()));
+
+        assert_eq!(
+            display_line_mismatch(contents, &mismatch),
+            "2 | dear world?
+  | ^^^^^ \"dear \"
+  |      ^^^^^ ${name}
+  |           ^ Expected \"!\", found \"?\"
+"
+        );
+    }
+
+    #[test]
+    fn line_mismatch_after_skip_picks_closest_line() {
+        let template = [
+            Match::MultipleLines,
+            Match::Text("a".into()),
+            Match::Text("b".into()),
+            Match::Text("c".into()),
+        ];
+        let item = Item::new(&[], &template);
+        let contents = b"x\nabx\nax\n";
+
+        let compiled = item.compile();
+        let mismatch = compiled
+            .line_mismatch(contents, &HashMap::new())
+            .expect("expected mismatch");
+        assert_eq!(mismatch.line_start, 2);
+        assert_eq!(mismatch.matched.len(), 2);
+        assert!(compiled.line_mismatch(b"x\nabc", &HashMap::new()).is_none());
+    }
+
+    #[test]
+    fn line_range_error_is_displayed_for_file() {
+        let contents = "a\nb\nc\nfoo\n";
+        let path = temp_file("line_range_error_is_displayed_for_file.txt", contents);
+        let template = [Match::LineRange { max: 1 }, Match::Text("foo".into())];
+        let err = Item::new(&[], &template)
+            .match_contents(&mut Cursor::new(contents.as_bytes()), &HashMap::new())
+            .err()
+            .expect("expected error");
+
+        let snippet = display_error_for_file(&path, &err);
+        assert!(snippet.ends_with(
+            "\n1 | a\n2 | b\n  | ^\n  | Expected a match within 1 skipped lines"
+        ));
+    }
+}
@@ -0,0 +1,121 @@
+extern crate specker;
+
+#[cfg(test)]
+mod errors {
+    use specker::{Options, ParseError, Spec, TemplateMatchError, TemplateWriteError};
+    use std::collections::HashMap;
+    use std::error::Error;
+    use std::io;
+    use std::path::PathBuf;
+
+    #[test]
+    fn parse_error_source_is_lex_error() {
+        let err = Spec::parse(Options::default(), b"## file: \xff")
+            .err()
+            .expect("expected error");
+        let lex_err = err.source().expect("expected lex error source");
+        assert!(lex_err.source().is_some(), "expected utf8 error source");
+    }
+
+    #[test]
+    fn match_io_error_source() {
+        let err = TemplateMatchError::Io(io::Error::new(io::ErrorKind::Other, "boom"));
+        assert_eq!(err.source().map(|e| e.to_string()), Some("boom".into()));
+    }
+
+    #[test]
+    fn walk_parse_error_source() {
+        let err: specker::Error = (
+            "spec.txt".into(),
+            Spec::parse(Options::default(), b"## file: \xff")
+                .err()
+                .expect("expected error"),
+        ).into();
+        assert!(err.source().is_some());
+    }
+
+    fn match_spec(spec: &[u8], contents: &[u8]) -> specker::Result<()> {
+        let spec = Spec::parse(Options::default(), spec)
+            .map_err(|e| (PathBuf::from("spec.txt"), e))?;
+        for item in spec.iter() {
+            item.match_contents(&mut io::Cursor::new(contents), &HashMap::new())
+                .map_err(|e| (PathBuf::from("output.txt"), e))?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn match_error_propagates_with_question_mark() {
+        match_spec(b"## file: output.txt\nhello", b"hello").expect("expected match");
+
+        match match_spec(b"## file: output.txt\nhello", b"hallo") {
+            Err(specker::Error::Match { path, err }) => {
+                assert_eq!(path, PathBuf::from("output.txt"));
+                assert_eq!(
+                    err.desc,
+                    TemplateMatchError::ExpectedText {
+                        expected: "hello".into(),
+                        found: "hallo".into(),
+                        found_line: "hallo".into(),
+                    }
+                );
+            }
+            other => panic!("expected match error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn write_error_converts_with_path() {
+        let err: specker::Error = (
+            PathBuf::from("output.txt"),
+            TemplateWriteError::MissingParam("name".into()),
+        ).into();
+        assert_eq!(
+            err.to_string(),
+            "Missing template param \"name\" in \"output.txt\""
+        );
+        assert!(err.source().is_some());
+    }
+
+    #[test]
+    fn expected_text_found_eof_shows_last_line() {
+        let err = TemplateMatchError::ExpectedTextFoundEof {
+            expected: "end".into(),
+            found: "body".into(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Expected \"end\", found end of file after \"body\""
+        );
+    }
+
+    #[test]
+    fn strict_parse_rejects_stray_marker() {
+        let contents = b"## file: a\nhello\n##\nbye";
+        let err = Spec::parse_strict(Options::default(), contents)
+            .err()
+            .expect("expected error");
+        err.assert_matches(&ParseError::EmptyItem, (2, 2), (2, 2))
+            .unwrap();
+
+        let spec = Spec::parse(Options::default(), contents).unwrap();
+        assert_eq!(spec.iter().count(), 2);
+    }
+
+    #[test]
+    fn strict_parse_accepts_params() {
+        let spec = Spec::parse_strict(Options::default(), b"## file: a\n## skip\nhello").unwrap();
+        assert_eq!(spec.iter().count(), 1);
+    }
+
+    #[test]
+    fn error_byte_and_line_ranges() {
+        let err = Spec::parse(Options::default(), b"## file: a\nhello\n## file: \"x\n")
+            .err()
+            .expect("expected error");
+
+        assert_eq!(err.lo.as_tuple(), (2, 9, 26));
+        assert_eq!(err.byte_range(), err.lo.byte..err.hi.byte);
+        assert_eq!(err.line_range(), 2..=2);
+    }
+}
@@ -0,0 +1,151 @@
+#![cfg(feature = "glob")]
+
+extern crate specker;
+
+#[cfg(test)]
+mod glob {
+    use specker::{Error, Options, Spec, SpecPath, TemplateMatchError};
+    use std::collections::HashMap;
+    use std::env;
+    use std::fs;
+    use std::io;
+    use std::path::PathBuf;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join("specker-tests").join("glob").join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn spec_path(contents: &str) -> SpecPath {
+        SpecPath {
+            spec: Spec::parse(Options::default(), contents.as_bytes()).unwrap(),
+            path: PathBuf::from("spec.txt"),
+        }
+    }
+
+    #[test]
+    fn pattern_matches_files_in_nested_dirs() {
+        let dir = temp_dir("pattern_matches_files_in_nested_dirs");
+        fs::create_dir_all(dir.join("src/a")).unwrap();
+        fs::write(dir.join("src/lib.rs"), "// generated\nmod a;").unwrap();
+        fs::write(dir.join("src/a/mod.rs"), "// generated\n").unwrap();
+        fs::write(dir.join("src/a/b.rs"), "fn b() {}").unwrap();
+        fs::write(dir.join("README.md"), "hello").unwrap();
+
+        let spec_path = spec_path(
+            "## file: **/*.rs
+// generated
+..",
+        );
+        let results = spec_path
+            .match_all(&dir, &HashMap::new())
+            .unwrap();
+
+        let paths: Vec<_> = results.keys().cloned().collect();
+        assert_eq!(
+            paths,
+            vec![
+                dir.join("src/a/b.rs"),
+                dir.join("src/a/mod.rs"),
+                dir.join("src/lib.rs"),
+            ]
+        );
+        assert!(results[&dir.join("src/lib.rs")].is_ok());
+        assert!(results[&dir.join("src/a/mod.rs")].is_ok());
+        assert!(results[&dir.join("src/a/b.rs")].is_err());
+    }
+
+    #[test]
+    fn single_star_does_not_cross_dirs() {
+        let dir = temp_dir("single_star_does_not_cross_dirs");
+        fs::create_dir_all(dir.join("src/a")).unwrap();
+        fs::write(dir.join("src/lib.rs"), "hello").unwrap();
+        fs::write(dir.join("src/a/mod.rs"), "hello").unwrap();
+
+        let spec_path = spec_path(
+            "## file: src/*.rs
+hello",
+        );
+        let results = spec_path
+            .match_all(&dir, &HashMap::new())
+            .unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert!(results[&dir.join("src/lib.rs")].is_ok());
+    }
+
+    #[test]
+    fn pattern_that_matches_nothing_is_not_found() {
+        let dir = temp_dir("pattern_that_matches_nothing_is_not_found");
+        fs::write(dir.join("a.txt"), "hello").unwrap();
+
+        let spec_path = spec_path(
+            "## file: *.txt
+## file: *.rs
+hello",
+        );
+        let results = spec_path
+            .match_all(&dir, &HashMap::new())
+            .unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert!(results[&dir.join("a.txt")].is_ok());
+        match results[&dir.join("*.rs")] {
+            Err(ref e) => match e.desc {
+                TemplateMatchError::Io(ref e) => assert_eq!(e.kind(), io::ErrorKind::NotFound),
+                ref other => panic!("expected io error, got {:?}", other),
+            },
+            Ok(()) => panic!("expected error"),
+        }
+    }
+
+    #[test]
+    fn first_failure_is_kept_for_file_matched_by_many_items() {
+        let dir = temp_dir("first_failure_is_kept_for_file_matched_by_many_items");
+        fs::write(dir.join("a.txt"), "hello").unwrap();
+
+        let spec_path = spec_path(
+            "## file: *.txt
+bye
+## file: a.txt
+hi
+## file: a.*
+hello",
+        );
+        let results = spec_path
+            .match_all(&dir, &HashMap::new())
+            .unwrap();
+
+        assert_eq!(results.len(), 1);
+        results[&dir.join("a.txt")]
+            .as_ref()
+            .err()
+            .expect("expected error")
+            .assert_matches(
+                &TemplateMatchError::ExpectedText {
+                    expected: "bye".into(),
+                    found: "hello".into(),
+                    found_line: "hello".into(),
+                },
+                (0, 0),
+                (0, 5),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn invalid_pattern_is_an_error() {
+        let dir = temp_dir("invalid_pattern_is_an_error");
+
+        let spec_path = spec_path(
+            "## file: src/[a.rs
+hello",
+        );
+        match spec_path.match_all(&dir, &HashMap::new()) {
+            Err(Error::Glob(_)) => (),
+            other => panic!("expected glob error, got {:?}", other),
+        }
+    }
+}
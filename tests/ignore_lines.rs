@@ -0,0 +1,85 @@
+#![cfg(feature = "regex")]
+
+extern crate specker;
+
+mod support;
+
+#[cfg(test)]
+mod ignore_lines {
+    use specker::{At, Match, MatchOptions, TemplateMatchError};
+    use std::collections::HashMap;
+    use support::new_item;
+
+    fn match_ignoring(
+        template: &[Match],
+        patterns: &[&str],
+        contents: &str,
+    ) -> Result<(), At<TemplateMatchError>> {
+        let options = MatchOptions {
+            ignore_line_patterns: patterns.iter().map(|p| p.to_string()).collect(),
+            ..MatchOptions::default()
+        };
+        let mut cursor = ::std::io::Cursor::new(contents.as_bytes());
+        new_item(template).match_contents_with(&mut cursor, &HashMap::new(), &options)
+    }
+
+    #[test]
+    fn volatile_lines_are_ignored() {
+        match_ignoring(
+            &[
+                Match::Text("start".into()),
+                Match::NewLine,
+                Match::Text("end".into()),
+            ],
+            &["^generated at ", "^/home/"],
+            "generated at 12:00\nstart\n/home/user/project\nend",
+        ).expect("expected match");
+    }
+
+    #[test]
+    fn ignored_last_line() {
+        match_ignoring(
+            &[Match::Text("start".into())],
+            &["^time: "],
+            "start\ntime: 12:00",
+        ).expect("expected match");
+    }
+
+    #[test]
+    fn error_position_points_to_original_line() {
+        let err = match_ignoring(
+            &[
+                Match::Text("start".into()),
+                Match::NewLine,
+                Match::Text("end".into()),
+            ],
+            &["^time: "],
+            "start\ntime: 12:00\ntime: 12:01\nfinish",
+        ).err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::ExpectedText {
+                expected: "end".into(),
+                found: "finish".into(),
+                found_line: "finish".into(),
+            },
+            (3, 0),
+            (3, 6),
+        ).unwrap();
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        let err = match_ignoring(&[], &["("], "")
+            .err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::InvalidIgnorePattern {
+                pattern: "(".into(),
+                message: String::new(),
+            },
+            (0, 0),
+            (0, 0),
+        ).unwrap();
+    }
+}
@@ -0,0 +1,145 @@
+extern crate specker;
+
+#[cfg(test)]
+mod include {
+    use specker::{Error, Match, Options, Spec, MAX_INCLUDE_DEPTH};
+    use std::collections::HashMap;
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    fn parse(files: &[(&str, &str)], path: &str) -> Result<Spec, Error> {
+        let files: HashMap<PathBuf, &str> = files
+            .iter()
+            .map(|&(path, contents)| (PathBuf::from(path), contents))
+            .collect();
+        let contents = files[Path::new(path)].as_bytes();
+        Spec::parse_with_resolver(Options::default(), contents, Path::new(path), |p| {
+            files
+                .get(p)
+                .map(|contents| contents.as_bytes().to_vec())
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "not found"))
+        })
+    }
+
+    #[test]
+    fn included_items_are_spliced_in_place() {
+        let spec = parse(
+            &[
+                (
+                    "specs/main.txt",
+                    "## file: a\nfirst\n## include: common/header.txt\n## file: c\nlast",
+                ),
+                ("specs/common/header.txt", "## file: b\nheader"),
+            ],
+            "specs/main.txt",
+        ).unwrap();
+
+        let files: Vec<_> = spec.iter().map(|item| item.get_param("file")).collect();
+        assert_eq!(files, vec![Some("a"), Some("b"), Some("c")]);
+
+        let ast = spec.into_ast();
+        assert_eq!(ast.items[0].origin, None);
+        assert_eq!(ast.items[1].origin, Some(PathBuf::from("specs/common/header.txt")));
+        assert_eq!(ast.items[1].template, vec![Match::Text("header".into())]);
+        assert_eq!(ast.items[1].span.0.line, 0);
+        assert_eq!(ast.items[2].origin, None);
+        assert!(ast.items[2].params.iter().all(|p| p.key != "include"));
+    }
+
+    #[test]
+    fn nested_includes_are_relative_to_including_file() {
+        let spec = parse(
+            &[
+                ("main.txt", "## include: a/one.txt"),
+                ("a/one.txt", "## include: b/two.txt"),
+                ("a/b/two.txt", "## file: x\ntwo"),
+            ],
+            "main.txt",
+        ).unwrap();
+
+        let ast = spec.into_ast();
+        assert_eq!(ast.items.len(), 1);
+        assert_eq!(ast.items[0].origin, Some(PathBuf::from("a/b/two.txt")));
+    }
+
+    #[test]
+    fn include_cycle_is_an_error() {
+        let err = parse(
+            &[
+                ("main.txt", "## include: other.txt"),
+                ("other.txt", "## include: main.txt"),
+            ],
+            "main.txt",
+        ).err()
+            .expect("expected error");
+
+        match err {
+            Error::IncludeCycle { path } => assert_eq!(path, PathBuf::from("main.txt")),
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_include_is_reported_at_including_item() {
+        let err = parse(&[("main.txt", "a\n## include: missing.txt")], "main.txt")
+            .err()
+            .expect("expected error");
+
+        match err {
+            Error::Include {
+                ref path,
+                ref include,
+                span,
+                ref err,
+            } => {
+                assert_eq!(path, &PathBuf::from("main.txt"));
+                assert_eq!(include, &PathBuf::from("missing.txt"));
+                assert_eq!(span.0.line, 1);
+                assert_eq!(err.kind(), io::ErrorKind::NotFound);
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_error_in_included_file_has_its_path() {
+        let err = parse(
+            &[("main.txt", "## include: bad.txt"), ("bad.txt", "## file: \"a\n")],
+            "main.txt",
+        ).err()
+            .expect("expected error");
+
+        match err {
+            Error::Parse { path, .. } => assert_eq!(path, PathBuf::from("bad.txt")),
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    fn chain(depth: usize) -> Vec<(String, String)> {
+        let mut files: Vec<_> = (0..depth)
+            .map(|i| (format!("{}.txt", i), format!("## include: {}.txt", i + 1)))
+            .collect();
+        files.push((format!("{}.txt", depth), "## file: x\nlast".into()));
+        files
+    }
+
+    #[test]
+    fn includes_nested_too_deep_are_an_error() {
+        let files = chain(MAX_INCLUDE_DEPTH);
+        let files: Vec<_> = files.iter().map(|&(ref p, ref c)| (&p[..], &c[..])).collect();
+        let spec = parse(&files, "0.txt").unwrap();
+        assert_eq!(spec.iter().count(), 1);
+
+        let files = chain(MAX_INCLUDE_DEPTH + 1);
+        let files: Vec<_> = files.iter().map(|&(ref p, ref c)| (&p[..], &c[..])).collect();
+        let err = parse(&files, "0.txt").err().expect("expected error");
+
+        match err {
+            Error::MaxDepthExceeded { path, max_depth } => {
+                assert_eq!(path, PathBuf::from(format!("{}.txt", MAX_INCLUDE_DEPTH + 1)));
+                assert_eq!(max_depth, MAX_INCLUDE_DEPTH);
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+}
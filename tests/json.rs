@@ -0,0 +1,70 @@
+#![cfg(feature = "serde")]
+
+extern crate specker;
+
+mod support;
+
+#[cfg(test)]
+mod json {
+    use specker::{FilePosition, Match, Options, Spec, TemplateMatchError};
+    use support::{match_item, new_item};
+
+    #[test]
+    fn match_error_to_json() {
+        let err = match_item(new_item(&[Match::Text("hello".into())]), &[], "help")
+            .err()
+            .expect("expected error");
+
+        assert_eq!(
+            err.to_json(),
+            concat!(
+                r#"{"kind":"expected_text","#,
+                r#""lo":{"line":0,"col":0,"byte":0},"hi":{"line":0,"col":4,"byte":4},"#,
+                r#""message":"Expected \"hello\", found \"help\"","#,
+                r#""expected":"hello","found":"help","found_line":"help"}"#
+            )
+        );
+    }
+
+    #[test]
+    fn block_mismatch_to_json() {
+        let err = TemplateMatchError::BlockMismatch {
+            expected: vec!["a".into(), "b".into()],
+            found: vec!["a".into(), "c".into()],
+            first_line: 0,
+        }.at(FilePosition::new(), FilePosition::new());
+
+        assert!(
+            err.to_json()
+                .ends_with(r#""expected":["a","b"],"found":["a","c"],"first_line":0}"#)
+        );
+        assert!(err.to_json().starts_with(r#"{"kind":"block_mismatch","#));
+    }
+
+    #[test]
+    fn parse_error_to_json() {
+        let options = Options {
+            skip_lines: "..",
+            marker: "##",
+            var_start: "${",
+            var_end: "}",
+            param_separator: ":",
+            escape: None,
+            allow_indented_markers: false,
+            case_insensitive_keys: false,
+            interpret_escapes: false,
+        };
+        let err = Spec::parse(options, b"hello ${name")
+            .err()
+            .expect("expected error");
+
+        assert_eq!(
+            err.to_json(),
+            concat!(
+                r#"{"kind":"lex","#,
+                r#""lo":{"line":0,"col":12,"byte":12},"hi":{"line":0,"col":12,"byte":12},"#,
+                r#""message":"Expected \"}\", found new line"}"#
+            )
+        );
+    }
+}
@@ -0,0 +1,95 @@
+extern crate specker;
+
+#[cfg(test)]
+mod lex {
+    use specker::{lex, LexError, Options, OptionsError, Spec, Token, TokenValue};
+    use std::iter::FusedIterator;
+
+    #[test]
+    fn lex_owned_tokens() {
+        let tokens: Vec<Token> = {
+            let contents = String::from("## file: a\nHello ${ name }\n..");
+            lex(Options::default(), contents.as_bytes())
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap()
+        };
+
+        let values: Vec<_> = tokens.iter().map(|t| t.value.clone()).collect();
+        assert_eq!(
+            values,
+            vec![
+                TokenValue::Key("file".into()),
+                TokenValue::Value("a".into()),
+                TokenValue::MatchText("Hello ".into()),
+                TokenValue::Var("name".into()),
+                TokenValue::MatchAnyNumberOfLines,
+            ]
+        );
+        assert_eq!(tokens[3].lo.line, 1);
+        assert_eq!(tokens[3].lo.col, 9);
+    }
+
+    #[test]
+    fn lex_stops_at_error() {
+        let results: Vec<_> = lex(Options::default(), b"Hello ${ name").unwrap().collect();
+
+        let err = results.last().unwrap().as_ref().err().expect("expected error");
+        assert_eq!(err.desc, LexError::ExpectedSequenceFoundNewline { expected: "}".into() });
+        assert_eq!(results.iter().filter(|r| r.is_err()).count(), 1);
+    }
+
+    #[test]
+    fn lex_validates_options() {
+        let options = Options {
+            skip_lines: "",
+            ..Options::default()
+        };
+        assert_eq!(
+            lex(options, b"hello").err().expect("expected error"),
+            OptionsError::Empty("skip_lines")
+        );
+    }
+
+    fn assert_fused<I: FusedIterator>(_: &I) {}
+
+    #[test]
+    fn iterators_are_fused() {
+        let mut tokens = lex(Options::default(), b"Hello ${ name").unwrap();
+        assert_fused(&tokens);
+        assert!(tokens.by_ref().last().unwrap().is_err());
+        assert!(tokens.next().is_none());
+        assert!(tokens.next().is_none());
+
+        let spec = Spec::parse(Options::default(), b"hello").unwrap();
+        assert_fused(&spec.iter());
+        #[cfg(feature = "walk")]
+        assert_fused(&specker::walk_spec_dir(
+            ::std::path::Path::new("."),
+            "txt",
+            Options::default(),
+        ));
+    }
+
+    #[test]
+    fn lex_param_value_continuation() {
+        let tokens: Vec<Token> = lex(Options::default(), b"## key: a\n## : b\n##:\nc")
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let values: Vec<_> = tokens.iter().map(|t| t.value.clone()).collect();
+        assert_eq!(
+            values,
+            vec![
+                TokenValue::Key("key".into()),
+                TokenValue::Value("a".into()),
+                TokenValue::Value("b".into()),
+                TokenValue::Value("".into()),
+                TokenValue::MatchText("c".into()),
+            ]
+        );
+        assert_eq!(tokens[2].lo.line, 1);
+        assert_eq!(tokens[2].lo.col, 5);
+    }
+}
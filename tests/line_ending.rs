@@ -0,0 +1,91 @@
+extern crate specker;
+
+mod support;
+
+#[cfg(test)]
+mod line_ending {
+    use specker::{detect_line_ending, At, LineEnding, Match, MatchOptions, TemplateMatchError};
+    use std::collections::HashMap;
+    use std::io::Cursor;
+    use support::new_item;
+
+    fn match_requiring(
+        template: &[Match],
+        contents: &str,
+        line_ending: LineEnding,
+    ) -> Result<(), At<TemplateMatchError>> {
+        let options = MatchOptions {
+            require_line_ending: Some(line_ending),
+            ..MatchOptions::default()
+        };
+        let mut cursor = Cursor::new(contents.as_bytes());
+        new_item(template).match_contents_with(&mut cursor, &HashMap::new(), &options)
+    }
+
+    #[test]
+    fn lf() {
+        assert_eq!(detect_line_ending(b"a\nb\n"), LineEnding::Lf);
+    }
+
+    #[test]
+    fn crlf() {
+        assert_eq!(detect_line_ending(b"a\r\nb\r\n"), LineEnding::CrLf);
+    }
+
+    #[test]
+    fn mixed() {
+        assert_eq!(detect_line_ending(b"a\r\nb\nc"), LineEnding::Mixed);
+    }
+
+    #[test]
+    fn no_newline() {
+        assert_eq!(detect_line_ending(b""), LineEnding::None);
+        assert_eq!(detect_line_ending(b"abc"), LineEnding::None);
+    }
+
+    #[test]
+    fn cr() {
+        assert_eq!(detect_line_ending(b"a\rb\r"), LineEnding::Cr);
+        assert_eq!(detect_line_ending(b"a\rb\r\n"), LineEnding::Mixed);
+    }
+
+    #[test]
+    fn detected_line_ending_of_input() {
+        let item = new_item(&[]);
+        let detected = item
+            .detected_line_ending(&mut Cursor::new(&b"a\r\nb\r\n"[..]))
+            .unwrap();
+        assert_eq!(detected, LineEnding::CrLf);
+    }
+
+    #[test]
+    fn required_line_ending_matches() {
+        let template = [Match::Text("a".into()), Match::MultipleLines];
+        match_requiring(&template, "a\r\nb\r\nc", LineEnding::CrLf).expect("expected match");
+    }
+
+    #[test]
+    fn wrong_line_ending_in_skipped_lines() {
+        let template = [Match::Text("a".into()), Match::MultipleLines];
+        let err = match_requiring(&template, "a\r\nb\nc", LineEnding::CrLf)
+            .err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::WrongLineEnding {
+                expected: LineEnding::CrLf,
+                found: LineEnding::Lf,
+            },
+            (1, 1),
+            (1, 2),
+        ).unwrap();
+    }
+
+    #[test]
+    fn any_line_ending_is_allowed_with_mixed() {
+        let template = [Match::Text("a".into()), Match::NewLine, Match::Text("b".into())];
+        match_requiring(&template, "a\r\nb", LineEnding::Mixed).expect("expected match");
+        match_requiring(&template, "a\nb", LineEnding::None)
+            .err()
+            .expect("expected error");
+    }
+}
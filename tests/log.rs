@@ -0,0 +1,61 @@
+#![cfg(feature = "log")]
+
+extern crate log;
+extern crate specker;
+
+mod support;
+
+// The logger is global, so this file has a single test.
+#[cfg(test)]
+mod log_messages {
+    use log::{self, Level, LevelFilter, Log, Metadata, Record};
+    use specker::Match;
+    use std::sync::Mutex;
+    use support::{match_item, new_item};
+
+    struct Collect;
+
+    static MESSAGES: Mutex<Vec<(Level, String)>> = Mutex::new(Vec::new());
+    static LOGGER: Collect = Collect;
+
+    impl Log for Collect {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            MESSAGES
+                .lock()
+                .unwrap()
+                .push((record.level(), record.args().to_string()));
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[test]
+    fn matching_is_traced() {
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(LevelFilter::Trace);
+
+        let template = [
+            Match::Text("a".into()),
+            Match::MultipleLines,
+            Match::Text("c".into()),
+        ];
+        match_item(new_item(&template), &[], "a\nb\nd")
+            .err()
+            .expect("expected error");
+
+        let messages = MESSAGES.lock().unwrap();
+        let has = |level: Level, text: &str| {
+            messages
+                .iter()
+                .any(|&(l, ref message)| l == level && message == text)
+        };
+        assert!(has(Level::Debug, "matching 3 line groups against 5 bytes"));
+        assert!(has(Level::Trace, "line 0, col 0: matching line [Text(\"a\")]"));
+        assert!(has(Level::Trace, "line 1, col 0: skipping line that does not match"));
+        assert!(has(Level::Trace, "line 2, col 0: skipping line that does not match"));
+    }
+}
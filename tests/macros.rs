@@ -0,0 +1,33 @@
+#![cfg(feature = "test-macros")]
+
+#[macro_use]
+extern crate specker;
+
+#[cfg(test)]
+mod macros {
+    use specker::{Item, Match};
+
+    fn item(template: &[Match]) -> Item {
+        Item::new(&[], template)
+    }
+
+    #[test]
+    fn assert_matches_passes() {
+        assert_matches!(item(&[Match::Text("hi".into())]), "hi");
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected \"hi\", found \"ho\"")]
+    fn assert_matches_panics_with_formatted_error() {
+        assert_matches!(item(&[Match::Text("hi".into())]), "ho");
+    }
+
+    #[test]
+    fn assert_contents_passes() {
+        let mut output = Vec::new();
+        item(&[Match::Text("hi".into())])
+            .write_contents(&mut output, &::std::collections::HashMap::new())
+            .unwrap();
+        assert_contents!(&output, "hi");
+    }
+}
@@ -4,9 +4,12 @@ mod support;
 
 #[cfg(test)]
 mod match_template_item {
-    use specker::Match;
+    use specker::{At, FilePosition, LineEnding, Match, MatchEvent, MatchObserver, MatchOptions};
+    use specker::Matcher;
     use specker::TemplateMatchError;
-    use support::{match_item, new_item};
+    use std::collections::HashMap;
+    use std::io::Cursor;
+    use support::{match_item, match_item_against_baseline, match_with, match_with_params, new_item};
 
     #[test]
     fn empty_item_matches_empty_file() {
@@ -22,6 +25,53 @@ mod match_template_item {
             .unwrap();
     }
 
+    #[test]
+    fn empty_file_against_every_template_shape() {
+        let matching: Vec<Vec<Match>> = vec![
+            vec![],
+            vec![Match::MultipleLines],
+            vec![Match::Text("".into())],
+            vec![Match::Var("v".into())],
+            vec![Match::CaptureLines("c".into())],
+        ];
+        for template in &matching {
+            for input in &["", "\u{feff}"] {
+                match_item(new_item(template), &[("v", "")], input)
+                    .unwrap_or_else(|e| panic!("{:?} should match {:?}: {:?}", template, input, e));
+            }
+        }
+
+        let new_line: Vec<Vec<Match>> = vec![
+            vec![Match::NewLine],
+            vec![Match::MultipleLines, Match::NewLine],
+            vec![Match::NewLine, Match::MultipleLines],
+        ];
+        for template in &new_line {
+            for input in &["", "\u{feff}"] {
+                let err = match_item(new_item(template), &[], input)
+                    .err()
+                    .expect("expected error");
+                err.assert_matches(&TemplateMatchError::ExpectedEol, (0, 0), (0, 0))
+                    .unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn empty_template_does_not_match_new_line() {
+        let err = match_item(new_item(&[]), &[], "\n")
+            .err()
+            .expect("expected error");
+        err.assert_matches(&TemplateMatchError::ExpectedEof, (0, 0), (0, 0))
+            .unwrap();
+
+        let err = match_item(new_item(&[Match::Text("".into())]), &[], "\n")
+            .err()
+            .expect("expected error");
+        err.assert_matches(&TemplateMatchError::ExpectedEof, (1, 0), (1, 0))
+            .unwrap();
+    }
+
     #[test]
     fn multiple_lines_item_should_match_empty_lines() {
         match_item(new_item(&[Match::MultipleLines]), &[], "").expect("expected match");
@@ -137,6 +187,7 @@ mod match_template_item {
             &TemplateMatchError::ExpectedText {
                 expected: "ho".into(),
                 found: "hi".into(),
+                found_line: "hi".into(),
             },
             (0, 0),
             (0, 2),
@@ -156,7 +207,10 @@ mod match_template_item {
         ).err()
             .expect("expected error");
         err.assert_matches(
-            &TemplateMatchError::ExpectedTextFoundEof("boo".into()),
+            &TemplateMatchError::ExpectedTextFoundEof {
+                expected: "boo".into(),
+                found: "world".into(),
+            },
             (3, 5),
             (3, 5),
         ).unwrap();
@@ -183,6 +237,7 @@ mod match_template_item {
             &TemplateMatchError::ExpectedText {
                 expected: "hello".into(),
                 found: "hell".into(),
+                found_line: "hell".into(),
             },
             (0, 0),
             (0, 4),
@@ -210,6 +265,7 @@ mod match_template_item {
             &TemplateMatchError::ExpectedText {
                 expected: "hello".into(),
                 found: "".into(),
+                found_line: "".into(),
             },
             (1, 0),
             (1, 0),
@@ -261,6 +317,7 @@ mod match_template_item {
             &TemplateMatchError::ExpectedText {
                 expected: "hello".into(),
                 found: "".into(),
+                found_line: "".into(),
             },
             (2, 0),
             (2, 0),
@@ -362,6 +419,7 @@ mod match_template_item {
             &TemplateMatchError::ExpectedText {
                 expected: "world".into(),
                 found: "".into(),
+                found_line: "".into(),
             },
             (1, 0),
             (1, 0),
@@ -432,6 +490,7 @@ mod match_template_item {
             &TemplateMatchError::ExpectedText {
                 expected: "world".into(),
                 found: "".into(),
+                found_line: "".into(),
             },
             (2, 0),
             (2, 0),
@@ -504,6 +563,7 @@ mod match_template_item {
             &TemplateMatchError::ExpectedText {
                 expected: "word".into(),
                 found: "world".into(),
+                found_line: "world".into(),
             },
             (0, 0),
             (0, 5),
@@ -531,9 +591,905 @@ mod match_template_item {
             &TemplateMatchError::ExpectedText {
                 expected: "b".into(),
                 found: " b".into(),
+                found_line: "a b".into(),
             },
             (0, 1),
             (0, 3),
         ).unwrap();
     }
+
+    #[test]
+    fn gap_match() {
+        match_item(
+            new_item(&[
+                Match::Text("fn ".into()),
+                Match::Gap(5),
+                Match::Text("()".into()),
+            ]),
+            &[],
+            "fn main()",
+        ).expect("expected match");
+    }
+
+    #[test]
+    fn gap_too_small_not_match() {
+        let err = match_item(
+            new_item(&[
+                Match::Text("fn ".into()),
+                Match::Gap(3),
+                Match::Text("()".into()),
+            ]),
+            &[],
+            "fn main()",
+        ).err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::ExpectedText {
+                expected: "()".into(),
+                found: "main()".into(),
+                found_line: "fn main()".into(),
+            },
+            (0, 3),
+            (0, 9),
+        ).unwrap();
+    }
+
+    #[test]
+    fn trailing_gap_match() {
+        match_item(
+            new_item(&[Match::Text("a".into()), Match::Gap(2), Match::NewLine]),
+            &[],
+            "abc
+",
+        ).expect("expected match");
+    }
+
+    #[test]
+    fn gap_does_not_cross_newline() {
+        let err = match_item(
+            new_item(&[Match::Text("a".into()), Match::Gap(5), Match::Text("b".into())]),
+            &[],
+            "a
+b",
+        ).err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::ExpectedText {
+                expected: "b".into(),
+                found: "".into(),
+                found_line: "a".into(),
+            },
+            (0, 1),
+            (0, 1),
+        ).unwrap();
+    }
+
+    #[test]
+    fn baselined_error_is_suppressed_but_new_error_surfaces() {
+        let template = [
+            Match::Text("a".into()),
+            Match::NewLine,
+            Match::Text("b".into()),
+            Match::NewLine,
+            Match::Text("c".into()),
+        ];
+
+        let baseline = match_item_against_baseline(new_item(&template), &[], "a
+x
+c", &[]);
+        assert_eq!(baseline.len(), 1);
+        baseline[0]
+            .assert_matches(
+                &TemplateMatchError::ExpectedText {
+                    expected: "b".into(),
+                    found: "x".into(),
+                    found_line: "x".into(),
+                },
+                (1, 0),
+                (1, 1),
+            )
+            .unwrap();
+
+        let errors = match_item_against_baseline(new_item(&template), &[], "a
+x
+y", &baseline);
+        assert_eq!(errors.len(), 1);
+        errors[0]
+            .assert_matches(
+                &TemplateMatchError::ExpectedText {
+                    expected: "c".into(),
+                    found: "y".into(),
+                    found_line: "y".into(),
+                },
+                (2, 0),
+                (2, 1),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn large_input_skip_and_match() {
+        let mut contents = String::new();
+        for i in 0..100_000 {
+            contents.push_str(&format!("line {}\r\n", i));
+        }
+        contents.push_str("end");
+
+        match_item(
+            new_item(&[Match::MultipleLines, Match::Text("end".into())]),
+            &[],
+            &contents,
+        ).expect("expected match");
+    }
+
+    #[test]
+    fn indent_match() {
+        match_item(
+            new_item(&[Match::Indent(4), Match::Text("hi".into())]),
+            &[],
+            "    hi",
+        ).expect("expected match");
+    }
+
+    #[test]
+    fn indent_under_not_match() {
+        let err = match_item(
+            new_item(&[Match::Indent(4), Match::Text("hi".into())]),
+            &[],
+            "  hi",
+        ).err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::ExpectedIndent {
+                expected: 4,
+                found: "  ".into(),
+            },
+            (0, 0),
+            (0, 2),
+        ).unwrap();
+    }
+
+    #[test]
+    fn indent_over_not_match() {
+        let err = match_item(
+            new_item(&[Match::Indent(2), Match::Text("hi".into())]),
+            &[],
+            "    hi",
+        ).err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::ExpectedIndent {
+                expected: 2,
+                found: "    ".into(),
+            },
+            (0, 0),
+            (0, 4),
+        ).unwrap();
+    }
+
+    #[test]
+    fn indent_tab_not_match() {
+        let err = match_item(
+            new_item(&[Match::Indent(1), Match::Text("hi".into())]),
+            &[],
+            "\thi",
+        ).err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::ExpectedIndent {
+                expected: 1,
+                found: "\t".into(),
+            },
+            (0, 0),
+            (0, 1),
+        ).unwrap();
+    }
+
+    #[test]
+    fn compiled_item_matches_many_inputs() {
+        let template = [Match::Text("hi".into()), Match::MultipleLines];
+        let item = new_item(&template);
+        let compiled = item.compile();
+        let params = HashMap::new();
+
+        for contents in &["hi", "hi\nthere", "hi\n\n"] {
+            compiled
+                .match_contents(&mut Cursor::new(contents.as_bytes()), &params)
+                .expect("expected match");
+        }
+
+        let err = compiled
+            .match_contents(&mut Cursor::new(&b"ho"[..]), &params)
+            .err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::ExpectedText {
+                expected: "hi".into(),
+                found: "ho".into(),
+                found_line: "ho".into(),
+            },
+            (0, 0),
+            (0, 2),
+        ).unwrap();
+    }
+
+    #[test]
+    fn bom_is_skipped() {
+        match_item(new_item(&[Match::Text("hi".into())]), &[], "\u{feff}hi")
+            .expect("expected match");
+    }
+
+    #[test]
+    fn bom_is_not_counted_as_column() {
+        let err = match_item(
+            new_item(&[Match::Text("hi".into())]),
+            &[],
+            "\u{feff}ho",
+        ).err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::ExpectedText {
+                expected: "hi".into(),
+                found: "ho".into(),
+                found_line: "ho".into(),
+            },
+            (0, 0),
+            (0, 2),
+        ).unwrap();
+        assert_eq!(err.lo.byte, 3);
+    }
+
+    #[test]
+    fn cr_line_endings_match() {
+        let tokens = &[
+            Match::Text("a".into()),
+            Match::MultipleLines,
+            Match::Text("c".into()),
+            Match::NewLine,
+        ];
+        match_item(new_item(tokens), &[], "a\rb\rb\rc\r").expect("expected match");
+    }
+
+    #[test]
+    fn cr_line_ending_mismatch_position() {
+        let tokens = &[
+            Match::Text("a".into()),
+            Match::NewLine,
+            Match::Text("b".into()),
+        ];
+        let err = match_item(new_item(tokens), &[], "a\rc\r")
+            .err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::ExpectedText {
+                expected: "b".into(),
+                found: "c".into(),
+                found_line: "c".into(),
+            },
+            (1, 0),
+            (1, 1),
+        ).unwrap();
+    }
+
+    #[test]
+    fn limited_match_within_limit() {
+        let tokens = &[Match::Text("hello".into())];
+        new_item(tokens)
+            .match_contents_limited(&mut Cursor::new(&b"hello"[..]), &HashMap::new(), 5)
+            .expect("expected match");
+    }
+
+    #[test]
+    fn limited_match_stops_reading_endless_input() {
+        let tokens = &[Match::MultipleLines];
+        let err = new_item(tokens)
+            .match_contents_limited(&mut ::std::io::repeat(b'a'), &HashMap::new(), 1024)
+            .err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::InputTooLarge { limit: 1024 },
+            (0, 0),
+            (0, 0),
+        ).unwrap();
+    }
+
+    #[test]
+    fn observed_match_reports_every_line() {
+        let tokens = &[
+            Match::Text("a".into()),
+            Match::NewLine,
+            Match::Text("b".into()),
+            Match::MultipleLines,
+            Match::Text("d".into()),
+        ];
+
+        let mut events = Vec::new();
+        new_item(tokens)
+            .match_contents_observed(
+                &mut Cursor::new(&b"a\nb\nc\nd"[..]),
+                &HashMap::new(),
+                &mut |e: &MatchEvent| events.push((e.tokens.len(), e.start, e.bytes)),
+            )
+            .expect("expected match");
+
+        assert_eq!(events, vec![(1, 0, 2), (1, 2, 2), (1, 4, 3)]);
+    }
+
+    #[derive(Default)]
+    struct Trace {
+        matched: Vec<usize>,
+        skipped: Vec<(usize, usize)>,
+        errors: Vec<String>,
+    }
+
+    impl MatchObserver for Trace {
+        fn on_line_matched(&mut self, event: &MatchEvent) {
+            self.matched.push(event.start);
+        }
+
+        fn on_skip_line(&mut self, pos: FilePosition) {
+            self.skipped.push((pos.line, pos.byte));
+        }
+
+        fn on_error(&mut self, err: &At<TemplateMatchError>) {
+            self.errors.push(err.desc.to_string());
+        }
+    }
+
+    #[test]
+    fn match_observer_sees_skipped_lines_and_error() {
+        let tokens = &[
+            Match::Text("a".into()),
+            Match::MultipleLines,
+            Match::Text("d".into()),
+            Match::NewLine,
+            Match::Text("e".into()),
+        ];
+
+        let mut trace = Trace::default();
+        let err = new_item(tokens)
+            .match_contents_observed(
+                &mut Cursor::new(&b"a\nb\nc\nd\nf"[..]),
+                &HashMap::new(),
+                &mut trace,
+            )
+            .err()
+            .expect("expected error");
+
+        assert_eq!(trace.matched, vec![0, 2]);
+        assert_eq!(trace.skipped, vec![(1, 2), (2, 4)]);
+        assert_eq!(trace.errors, vec![err.desc.to_string()]);
+    }
+
+    #[test]
+    fn match_spans_of_tokens() {
+        let tokens = &[
+            Match::Text("a ".into()),
+            Match::Var("x".into()),
+            Match::MultipleLines,
+            Match::Indent(2),
+            Match::Text("c".into()),
+            Match::Gap(3),
+            Match::Text("d".into()),
+            Match::MultipleLines,
+        ];
+        let mut params = HashMap::new();
+        params.insert("x", "bb");
+
+        let spans = new_item(tokens)
+            .match_spans(&mut Cursor::new(&b"a bb\nskip\n  c..d\ntail\n"[..]), &params)
+            .expect("expected match");
+
+        assert_eq!(
+            spans,
+            vec![
+                (Match::Text("a ".into()), 0..2),
+                (Match::Var("x".into()), 2..4),
+                (Match::MultipleLines, 5..10),
+                (Match::Indent(2), 10..12),
+                (Match::Text("c".into()), 12..13),
+                (Match::Gap(3), 13..15),
+                (Match::Text("d".into()), 15..16),
+                (Match::MultipleLines, 17..22),
+            ]
+        );
+    }
+
+    #[test]
+    fn match_spans_returns_error() {
+        let tokens = &[Match::Text("a".into())];
+        new_item(tokens)
+            .match_spans(&mut Cursor::new(&b"b"[..]), &HashMap::new())
+            .err()
+            .expect("expected error");
+    }
+
+    #[test]
+    fn many_items_match_shared_bytes() {
+        let contents = b"header\nbody 1\nbody 2\nfooter";
+        let templates = [
+            vec![Match::Text("header".into()), Match::MultipleLines],
+            vec![Match::MultipleLines, Match::Text("footer".into())],
+            vec![
+                Match::MultipleLines,
+                Match::Text("body ".into()),
+                Match::Gap(1),
+                Match::MultipleLines,
+            ],
+        ];
+
+        for template in &templates {
+            let item = new_item(template);
+            for _ in 0..100 {
+                item.match_bytes(contents, &HashMap::new())
+                    .expect("expected match");
+            }
+        }
+
+        new_item(&[Match::Text("footer".into())])
+            .match_bytes(contents, &HashMap::new())
+            .err()
+            .expect("expected error");
+    }
+
+    struct CountingReader<R> {
+        inner: R,
+        read: usize,
+    }
+
+    impl<R: ::std::io::Read> ::std::io::Read for CountingReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> ::std::io::Result<usize> {
+            let bytes = self.inner.read(buf)?;
+            self.read += bytes;
+            Ok(bytes)
+        }
+    }
+
+    #[test]
+    fn prefix_template_reads_only_prefix() {
+        use std::io::Read;
+
+        let tokens = &[
+            Match::Text("header".into()),
+            Match::NewLine,
+            Match::Text("second".into()),
+            Match::MultipleLines,
+        ];
+        let mut input = CountingReader {
+            inner: Cursor::new(&b"header\nsecond\n"[..]).chain(::std::io::repeat(b'x')),
+            read: 0,
+        };
+
+        new_item(tokens)
+            .match_contents(&mut input, &HashMap::new())
+            .expect("expected match");
+        assert!(input.read < 64 * 1024);
+    }
+
+    #[test]
+    fn prefix_template_with_cr_lf_and_mismatch() {
+        let tokens = &[
+            Match::Text("a".into()),
+            Match::NewLine,
+            Match::Text("b".into()),
+            Match::MultipleLines,
+        ];
+
+        new_item(tokens)
+            .match_contents(&mut Cursor::new(&b"a\r\nb\r\nc\r\nd"[..]), &HashMap::new())
+            .expect("expected match");
+        let err = new_item(tokens)
+            .match_contents(&mut Cursor::new(&b"a\r\nc\r\nd"[..]), &HashMap::new())
+            .err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::ExpectedText {
+                expected: "b".into(),
+                found: "c".into(),
+                found_line: "c".into(),
+            },
+            (1, 0),
+            (1, 1),
+        ).unwrap();
+    }
+
+    #[test]
+    fn match_bool_and_matches_str() {
+        let tokens = &[Match::Text("hi".into())];
+
+        assert!(new_item(tokens).match_bool(&mut Cursor::new(&b"hi"[..]), &HashMap::new()));
+        assert!(!new_item(tokens).match_bool(&mut Cursor::new(&b"ho"[..]), &HashMap::new()));
+        assert!(new_item(tokens).matches_str("hi", &HashMap::new()));
+        assert!(!new_item(tokens).matches_str("hip", &HashMap::new()));
+    }
+
+    #[test]
+    fn matcher_matches_many_inputs() {
+        let tokens = &[Match::Text("hi".into())];
+        let item = new_item(tokens);
+        let mut matcher = Matcher::new(&item);
+
+        matcher
+            .match_reader(&mut Cursor::new(&b"hi"[..]), &HashMap::new())
+            .expect("expected match");
+        assert!(matcher.match_reader(&mut Cursor::new(&b"hip"[..]), &HashMap::new()).is_err());
+        matcher
+            .match_reader(&mut Cursor::new(&b"hi"[..]), &HashMap::new())
+            .expect("expected match");
+
+        let err = matcher
+            .match_file(::std::path::Path::new("does-not-exist.txt"), &HashMap::new())
+            .err()
+            .expect("expected error");
+        match err.desc {
+            TemplateMatchError::Io(_) => (),
+            other => panic!("expected io error but got {:?}", other),
+        }
+    }
+
+    fn normalize_whitespace() -> MatchOptions {
+        MatchOptions {
+            normalize_whitespace: true,
+            ..MatchOptions::default()
+        }
+    }
+
+    #[test]
+    fn normalized_whitespace_run_matches_single_space() {
+        match_with(&[Match::Text("a b".into())], "a    b", &normalize_whitespace())
+            .expect("expected match");
+    }
+
+    #[test]
+    fn normalized_tabs_match_spaces() {
+        match_with(&[Match::Text("a  b".into())], "a\t \tb", &normalize_whitespace())
+            .expect("expected match");
+    }
+
+    #[test]
+    fn whitespace_is_exact_by_default() {
+        let err = match_with(&[Match::Text("a b".into())], "a    b", &MatchOptions::default())
+            .err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::ExpectedText {
+                expected: "a b".into(),
+                found: "a    b".into(),
+                found_line: "a    b".into(),
+            },
+            (0, 0),
+            (0, 6),
+        ).unwrap();
+    }
+
+    #[test]
+    fn normalized_whitespace_is_still_required() {
+        let err = match_with(&[Match::Text("a b".into())], "ab", &normalize_whitespace())
+            .err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::ExpectedText {
+                expected: "a b".into(),
+                found: "ab".into(),
+                found_line: "ab".into(),
+            },
+            (0, 0),
+            (0, 2),
+        ).unwrap();
+    }
+
+    #[test]
+    fn normalized_whitespace_in_var_values() {
+        let template = [Match::Var("v".into())];
+        match_with_params(&template, &[("v", "x y")], "x \t y", &normalize_whitespace())
+            .expect("expected match");
+    }
+
+    #[test]
+    fn normalized_whitespace_keeps_indent_exact() {
+        let template = [Match::Indent(2), Match::Text("a".into())];
+        match_with(&template, "  a", &normalize_whitespace()).expect("expected match");
+        match_with(&template, "    a", &normalize_whitespace())
+            .err()
+            .expect("expected error");
+    }
+
+    #[test]
+    fn normalized_whitespace_after_newline() {
+        let template = [
+            Match::Text("a".into()),
+            Match::NewLine,
+            Match::Text("b  =  c".into()),
+        ];
+        match_with(&template, "a\nb = c", &normalize_whitespace()).expect("expected match");
+    }
+
+    fn dedent() -> MatchOptions {
+        MatchOptions {
+            dedent: true,
+            ..MatchOptions::default()
+        }
+    }
+
+    fn dedent_block() -> Vec<Match> {
+        vec![
+            Match::Text("    fn main() {".into()),
+            Match::NewLine,
+            Match::Text("    }".into()),
+        ]
+    }
+
+    #[test]
+    fn dedented_spaces_block_matches_tab_block() {
+        match_with(&dedent_block(), "\tfn main() {\n\t}", &dedent()).expect("expected match");
+    }
+
+    #[test]
+    fn indentation_is_exact_by_default() {
+        let err = match_with(&dedent_block(), "\tfn main() {\n\t}", &MatchOptions::default())
+            .err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::ExpectedText {
+                expected: "    fn main() {".into(),
+                found: "\tfn main() {".into(),
+                found_line: "\tfn main() {".into(),
+            },
+            (0, 0),
+            (0, 12),
+        ).unwrap();
+    }
+
+    #[test]
+    fn dedent_compares_relative_indentation() {
+        let template = [
+            Match::Text("    a".into()),
+            Match::NewLine,
+            Match::Text("        b".into()),
+        ];
+
+        match_with(&template, "\ta\n\t    b", &dedent()).expect("expected match");
+        let err = match_with(&template, "\ta\n\tb", &dedent())
+            .err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::ExpectedText {
+                expected: "    b".into(),
+                found: "b".into(),
+                found_line: "\tb".into(),
+            },
+            (1, 1),
+            (1, 2),
+        ).unwrap();
+    }
+
+    #[test]
+    fn dedent_ignores_blank_lines_for_common_indentation() {
+        let template = [
+            Match::Text("  a".into()),
+            Match::NewLine,
+            Match::NewLine,
+            Match::Text("  b".into()),
+        ];
+
+        match_with(&template, "a\n\nb", &dedent()).expect("expected match");
+        match_with(&template, "    a\n  \n    b", &dedent()).expect("expected match");
+    }
+
+    #[test]
+    fn dedented_var_line_has_no_indentation() {
+        let template = [
+            Match::Text("  a".into()),
+            Match::NewLine,
+            Match::Var("x".into()),
+        ];
+
+        match_with_params(&template, &[("x", "b")], "  a\nb", &dedent()).expect("expected match");
+    }
+
+    #[test]
+    fn dedent_considers_indentation_of_unread_lines() {
+        let template = [Match::Text("a".into()), Match::MultipleLines];
+        let contents = "  a\n  b\nc\n";
+        let compiled = new_item(&template).compile();
+
+        let streamed = match_with(&template, contents, &dedent());
+        let whole = compiled.match_bytes_with(contents.as_bytes(), &HashMap::new(), &dedent());
+        assert_eq!(streamed, whole);
+        assert!(streamed.is_err());
+    }
+
+    fn ignore_final_newline() -> MatchOptions {
+        MatchOptions {
+            ignore_final_newline: true,
+            ..MatchOptions::default()
+        }
+    }
+
+    #[test]
+    fn ignored_final_newline_can_be_missing() {
+        let template = [Match::Text("hello".into()), Match::NewLine];
+
+        match_with(&template, "hello", &ignore_final_newline()).expect("expected match");
+        match_with(&template, "hello\n", &ignore_final_newline()).expect("expected match");
+    }
+
+    #[test]
+    fn missing_final_newline_fails_by_default() {
+        let template = [Match::Text("hello".into()), Match::NewLine];
+        let err = match_with(&template, "hello", &MatchOptions::default())
+            .err()
+            .expect("expected error");
+        err.assert_matches(&TemplateMatchError::ExpectedEol, (0, 5), (0, 5))
+            .unwrap();
+    }
+
+    #[test]
+    fn ignored_final_newline_still_fails_on_extra_lines() {
+        let template = [Match::Text("hello".into()), Match::NewLine];
+        let err = match_with(&template, "hello\n\n", &ignore_final_newline())
+            .err()
+            .expect("expected error");
+        err.assert_matches(&TemplateMatchError::ExpectedEof, (2, 0), (2, 0))
+            .unwrap();
+    }
+
+    #[test]
+    fn only_one_final_newline_can_be_missing() {
+        let template = [Match::Text("hello".into()), Match::NewLine, Match::NewLine];
+
+        match_with(&template, "hello\n", &ignore_final_newline()).expect("expected match");
+        let err = match_with(&template, "hello", &ignore_final_newline())
+            .err()
+            .expect("expected error");
+        err.assert_matches(&TemplateMatchError::ExpectedEol, (0, 5), (0, 5))
+            .unwrap();
+    }
+
+    fn normalize_crlf() -> MatchOptions {
+        MatchOptions {
+            normalize_crlf: true,
+            ..MatchOptions::default()
+        }
+    }
+
+    #[test]
+    fn normalized_crlf_matches_multiline_var() {
+        let template = [Match::Text("value: ".into()), Match::Var("v".into())];
+        let params = [("v", "a\nb")];
+
+        match_with_params(&template, &params, "value: a\r\nb", &normalize_crlf())
+            .expect("expected match");
+        match_with_params(&template, &params, "value: a\r\nb", &MatchOptions::default())
+            .err()
+            .expect("expected error");
+    }
+
+    #[test]
+    fn normalized_crlf_error_points_to_original_input() {
+        let template = [
+            Match::Text("a".into()),
+            Match::NewLine,
+            Match::Text("b".into()),
+            Match::NewLine,
+            Match::Text("c".into()),
+        ];
+        let err = match_with(&template, "a\r\nb\r\nd", &normalize_crlf())
+            .err()
+            .expect("expected error");
+
+        err.assert_matches(
+            &TemplateMatchError::ExpectedText {
+                expected: "c".into(),
+                found: "d".into(),
+                found_line: "d".into(),
+            },
+            (2, 0),
+            (2, 1),
+        ).unwrap();
+        assert_eq!((err.lo.byte, err.hi.byte), (6, 7));
+    }
+
+    #[test]
+    fn normalized_crlf_error_at_line_end_points_to_carriage_return() {
+        let template = [
+            Match::Text("a".into()),
+            Match::NewLine,
+            Match::Text("b".into()),
+            Match::Text("c".into()),
+        ];
+        let err = match_with(&template, "a\r\nb\r\nc", &normalize_crlf())
+            .err()
+            .expect("expected error");
+
+        assert_eq!((err.lo.line, err.lo.col, err.lo.byte), (1, 1, 4));
+    }
+
+    #[test]
+    fn normalized_crlf_still_checks_original_line_endings() {
+        let template = [Match::Text("a".into()), Match::NewLine, Match::Text("b".into())];
+        let options = MatchOptions {
+            require_line_ending: Some(LineEnding::CrLf),
+            ..normalize_crlf()
+        };
+
+        match_with(&template, "a\r\nb", &options).expect("expected match");
+    }
+
+    fn within(max: usize) -> Vec<Match> {
+        vec![
+            Match::LineRange { max: max },
+            Match::Text("fn main() {".into()),
+            Match::MultipleLines,
+        ]
+    }
+
+    #[test]
+    fn line_range_content_within_window_matches() {
+        match_item(new_item(&within(2)), &[], "fn main() {\n}").expect("expected match");
+        match_item(new_item(&within(2)), &[], "//\n//\nfn main() {\n}").expect("expected match");
+    }
+
+    #[test]
+    fn line_range_content_after_window_fails() {
+        let err = match_item(new_item(&within(2)), &[], "//\n//\n//\nfn main() {\n}")
+            .err()
+            .expect("expected error");
+        err.assert_matches(&TemplateMatchError::ExpectedMatchWithin { max: 2 }, (2, 0), (2, 2))
+            .unwrap();
+    }
+
+    #[test]
+    fn line_range_empty_window_requires_content_on_first_line() {
+        match_item(new_item(&within(0)), &[], "fn main() {").expect("expected match");
+        let err = match_item(new_item(&within(0)), &[], "\nfn main() {")
+            .err()
+            .expect("expected error");
+        err.assert_matches(&TemplateMatchError::ExpectedMatchWithin { max: 0 }, (0, 0), (0, 0))
+            .unwrap();
+    }
+
+    #[test]
+    fn line_range_window_after_matched_line_starts_at_next_line() {
+        let template = [
+            Match::Text("a".into()),
+            Match::LineRange { max: 1 },
+            Match::Text("b".into()),
+        ];
+
+        match_item(new_item(&template), &[], "a\nx\nb").expect("expected match");
+        let err = match_item(new_item(&template), &[], "a\nx\ny\nb")
+            .err()
+            .expect("expected error");
+        err.assert_matches(&TemplateMatchError::ExpectedMatchWithin { max: 1 }, (2, 0), (2, 1))
+            .unwrap();
+    }
+
+    #[test]
+    fn line_range_window_ending_at_end_of_input_fails() {
+        let err = match_item(new_item(&within(2)), &[], "//\n//\n")
+            .err()
+            .expect("expected error");
+        err.assert_matches(&TemplateMatchError::ExpectedMatchWithin { max: 2 }, (2, 0), (2, 0))
+            .unwrap();
+
+        let err = match_item(new_item(&within(2)), &[], "//\n//\n//")
+            .err()
+            .expect("expected error");
+        err.assert_matches(&TemplateMatchError::ExpectedMatchWithin { max: 2 }, (2, 0), (2, 2))
+            .unwrap();
+    }
+
+    #[test]
+    fn trailing_line_range_requires_end_of_input() {
+        let template = [Match::Text("a".into()), Match::LineRange { max: 1 }];
+
+        match_item(new_item(&template), &[], "a").expect("expected match");
+        match_item(new_item(&template), &[], "a\nb\n").expect("expected match");
+        let err = match_item(new_item(&template), &[], "a\nb\nc\nd\n")
+            .err()
+            .expect("expected error");
+        err.assert_matches(&TemplateMatchError::ExpectedEof, (2, 0), (2, 1))
+            .unwrap();
+    }
 }
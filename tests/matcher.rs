@@ -0,0 +1,70 @@
+extern crate specker;
+
+mod support;
+
+// The allocation counter is global, so this file has a single test.
+#[cfg(test)]
+mod matcher {
+    use specker::{Match, Matcher};
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use support::new_item;
+
+    struct CountingAlloc;
+
+    static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAlloc = CountingAlloc;
+
+    fn allocations<F: FnMut()>(mut f: F) -> usize {
+        let before = ALLOCATIONS.load(Ordering::SeqCst);
+        f();
+        ALLOCATIONS.load(Ordering::SeqCst) - before
+    }
+
+    #[test]
+    fn matcher_allocates_less_than_item_across_files() {
+        let template = [
+            Match::Text("hello".into()),
+            Match::MultipleLines,
+            Match::Text("bye".into()),
+        ];
+        let item = new_item(&template);
+        let contents = format!("hello\n{}bye", "line\n".repeat(100));
+
+        let item_allocations = allocations(|| {
+            for _ in 0..100 {
+                item.match_contents(&mut contents.as_bytes(), &[][..])
+                    .expect("expected match");
+            }
+        });
+
+        let mut matcher = Matcher::new(&item);
+        let matcher_allocations = allocations(|| {
+            for _ in 0..100 {
+                matcher
+                    .match_reader(&mut contents.as_bytes(), &[][..])
+                    .expect("expected match");
+            }
+        });
+
+        assert!(
+            matcher_allocations < item_allocations,
+            "{} allocations with matcher, {} without",
+            matcher_allocations,
+            item_allocations
+        );
+    }
+}
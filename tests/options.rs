@@ -0,0 +1,238 @@
+extern crate specker;
+
+#[cfg(test)]
+mod options {
+    use specker::{Match, Options, OptionsError, OwnedOptions, ParseError, Spec};
+
+    fn owned_options() -> OwnedOptions {
+        OwnedOptions {
+            skip_lines: String::from(".."),
+            marker: String::from("##"),
+            var_start: String::from("${"),
+            var_end: String::from("}"),
+            param_separator: String::from(":"),
+            escape: None,
+            allow_indented_markers: false,
+            case_insensitive_keys: false,
+            interpret_escapes: false,
+        }
+    }
+
+    #[test]
+    fn owned_options_round_trip() {
+        let owned = owned_options();
+        let options = Options::from(&owned);
+        assert_eq!(options.to_owned(), owned);
+    }
+
+    #[test]
+    fn parse_with_borrowed_owned_options() {
+        let owned = owned_options();
+        let spec = Spec::parse((&owned).into(), b"## file: a\nhello").unwrap();
+        let item = spec.iter().next().expect("expected item");
+        assert_eq!(item.get_param("file"), Some("a"));
+    }
+
+    #[test]
+    fn default_options_are_valid() {
+        assert_eq!(Options::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn empty_marker_is_invalid() {
+        let options = Options {
+            marker: "",
+            ..Options::default()
+        };
+        assert_eq!(options.validate(), Err(OptionsError::Empty("marker")));
+    }
+
+    #[test]
+    fn new_line_in_options_is_invalid() {
+        let options = Options {
+            skip_lines: "..\n",
+            ..Options::default()
+        };
+        assert_eq!(options.validate(), Err(OptionsError::NewLine("skip_lines")));
+
+        let options = Options {
+            var_end: "\r}",
+            ..Options::default()
+        };
+        assert_eq!(options.validate(), Err(OptionsError::NewLine("var_end")));
+    }
+
+    #[test]
+    fn var_start_prefix_of_marker_is_invalid() {
+        let options = Options {
+            var_start: "#",
+            ..Options::default()
+        };
+        assert_eq!(
+            options.validate(),
+            Err(OptionsError::Overlap {
+                prefix: "var_start",
+                other: "marker",
+            })
+        );
+    }
+
+    #[test]
+    fn equal_var_delimiters_are_invalid() {
+        let options = Options {
+            var_start: "%",
+            var_end: "%",
+            ..Options::default()
+        };
+        assert_eq!(
+            options.validate(),
+            Err(OptionsError::Overlap {
+                prefix: "var_start",
+                other: "var_end",
+            })
+        );
+    }
+
+    #[test]
+    fn parse_rejects_invalid_options() {
+        let options = Options {
+            skip_lines: "",
+            ..Options::default()
+        };
+        let err = Spec::parse(options, b"hello").err().expect("expected error");
+        assert_eq!(
+            err.desc,
+            ParseError::InvalidOptions(OptionsError::Empty("skip_lines"))
+        );
+    }
+
+    #[test]
+    fn custom_param_separator() {
+        let options = Options {
+            param_separator: "=",
+            ..Options::default()
+        };
+        let spec = Spec::parse(options, b"## file = a:b\nhello").unwrap();
+        let item = spec.iter().next().expect("expected item");
+        assert_eq!(item.get_param("file"), Some("a:b"));
+    }
+
+    #[test]
+    fn empty_param_separator_is_invalid() {
+        let options = Options {
+            param_separator: "",
+            ..Options::default()
+        };
+        assert_eq!(options.validate(), Err(OptionsError::Empty("param_separator")));
+    }
+
+    #[test]
+    fn param_separator_prefix_of_marker_is_invalid() {
+        let options = Options {
+            param_separator: "#",
+            ..Options::default()
+        };
+        assert_eq!(
+            options.validate(),
+            Err(OptionsError::Overlap {
+                prefix: "param_separator",
+                other: "marker",
+            })
+        );
+    }
+
+    #[test]
+    fn var_start_prefix_of_param_separator_is_invalid() {
+        let options = Options {
+            param_separator: "${=",
+            ..Options::default()
+        };
+        assert_eq!(
+            options.validate(),
+            Err(OptionsError::Overlap {
+                prefix: "var_start",
+                other: "param_separator",
+            })
+        );
+    }
+
+    #[test]
+    fn escaped_marker_line_is_content() {
+        let options = Options {
+            escape: Some("\\"),
+            ..Options::default()
+        };
+        let spec = Spec::parse(options, b"## file: a\n\\## title\nhello").unwrap();
+        let item = spec.iter().next().expect("expected item");
+        assert_eq!(item.get_params("file"), vec!["a"]);
+
+        let mut contents = ::std::io::Cursor::new(&b"## title\nhello"[..]);
+        item.match_contents(&mut contents, &::std::collections::HashMap::new())
+            .expect("expected match");
+    }
+
+    #[test]
+    fn escape_prefix_of_marker_is_invalid() {
+        let options = Options {
+            escape: Some("#"),
+            ..Options::default()
+        };
+        assert_eq!(
+            options.validate(),
+            Err(OptionsError::Overlap {
+                prefix: "escape",
+                other: "marker",
+            })
+        );
+    }
+
+    #[test]
+    fn indented_marker_positions() {
+        let options = Options {
+            allow_indented_markers: true,
+            ..Options::default()
+        };
+        let spec = Spec::parse(options, b"hello\n    ## file: a\n    text").unwrap();
+        let items: Vec<_> = spec.iter().collect();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].get_param("file"), Some("a"));
+
+        let tokens: Vec<_> = ::specker::lex(options, b"    ## file: a")
+            .unwrap()
+            .map(|t| t.unwrap())
+            .collect();
+        assert_eq!((tokens[0].lo.col, tokens[0].hi.col), (7, 11));
+    }
+
+    #[test]
+    fn escapes_are_literal_by_default() {
+        let spec = Spec::parse(Options::default(), br"a\nb\tc").unwrap();
+        let item = spec.iter().next().unwrap();
+        assert_eq!(item.template, &[Match::Text(r"a\nb\tc".into())][..]);
+    }
+
+    #[test]
+    fn interpreted_escapes_split_lines() {
+        let options = Options {
+            interpret_escapes: true,
+            ..Options::default()
+        };
+        let spec = Spec::parse(options, br"a\n\tb${x}\\n\q\n").unwrap();
+        let item = spec.iter().next().unwrap();
+        assert_eq!(
+            item.template,
+            &[
+                Match::Text("a".into()),
+                Match::NewLine,
+                Match::Text("\tb".into()),
+                Match::Var("x".into()),
+                Match::Text(r"\n\q".into()),
+                Match::NewLine,
+            ][..]
+        );
+
+        let params = [("x", "c")];
+        assert!(item.matches_str("a\n\tbc\\n\\q\n", &params[..]));
+        assert!(!item.matches_str(r"a\n\tbc\n\q\n", &params[..]));
+    }
+}
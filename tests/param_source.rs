@@ -0,0 +1,82 @@
+extern crate specker;
+
+mod support;
+
+#[cfg(test)]
+mod param_source {
+    use specker::{Match, OwnedParams, ParamSource};
+    use std::collections::HashMap;
+    use support::new_item;
+
+    fn template() -> Vec<Match> {
+        vec![Match::Text("hello ".into()), Match::Var("name".into())]
+    }
+
+    struct Greeting {
+        name: String,
+    }
+
+    impl ParamSource for Greeting {
+        fn get(&self, key: &str) -> Option<&str> {
+            match key {
+                "name" => Some(&self.name),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn str_map_params() {
+        let mut params = HashMap::new();
+        params.insert("name", "world");
+        assert!(new_item(&template()).matches_str("hello world", &params));
+    }
+
+    #[test]
+    fn string_map_params() {
+        let mut params = HashMap::new();
+        params.insert(String::from("name"), String::from("world"));
+        assert!(new_item(&template()).matches_str("hello world", &OwnedParams::new(&params)));
+    }
+
+    #[test]
+    fn slice_params() {
+        let params = [("name", "world")];
+        assert!(new_item(&template()).matches_str("hello world", &params[..]));
+        assert!(!new_item(&template()).matches_str("hello there", &params[..]));
+    }
+
+    #[test]
+    fn struct_params() {
+        let params = Greeting {
+            name: "world".into(),
+        };
+        let mut output = Vec::new();
+        new_item(&template())
+            .write_contents(&mut output, &params)
+            .unwrap();
+        assert_eq!(output, b"hello world");
+    }
+
+    fn owned_params(name: &str) -> HashMap<String, String> {
+        let mut params = HashMap::new();
+        params.insert(String::from("name"), name.to_string());
+        params
+    }
+
+    #[test]
+    fn owned_map_params_for_write_and_match() {
+        let item_template = template();
+        let item = new_item(&item_template);
+
+        let mut output = Vec::new();
+        let world = owned_params("world");
+        item.write_contents(&mut output, &OwnedParams::new(&world)).unwrap();
+        assert_eq!(output, b"hello world");
+
+        item.match_contents(&mut &output[..], &OwnedParams::new(&world))
+            .expect("expected match");
+        let there = owned_params("there");
+        assert!(item.match_contents(&mut &output[..], &OwnedParams::new(&there)).is_err());
+    }
+}
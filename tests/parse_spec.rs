@@ -0,0 +1,418 @@
+extern crate specker;
+
+#[cfg(test)]
+mod parse_spec {
+    use specker::ast;
+    use specker::{FilePosition, LexError, Match, Options, ParseError, Spec, TokenValueRef};
+    use std::collections::HashMap;
+    use std::io::Cursor;
+
+    #[test]
+    fn parse_with_tokens() {
+        let (spec, tokens) = Spec::parse_with_tokens(
+            Options::default(),
+            b"## file: a
+..
+Hello ${ name }
+## file: b
+bye",
+        ).unwrap();
+
+        assert_eq!(spec.iter().count(), 2);
+        assert_eq!(tokens.len(), 8);
+        assert_eq!(tokens[0].value, TokenValueRef::Key("file"));
+        assert_eq!(tokens[4].value, TokenValueRef::Var("name"));
+        assert_eq!(tokens[4].lo.line, 2);
+        assert_eq!(tokens[7].value, TokenValueRef::MatchText("bye"));
+    }
+
+    #[test]
+    fn parse_with_tokens_returns_lex_error() {
+        let err = Spec::parse_with_tokens(Options::default(), b"Hello ${ name")
+            .err()
+            .expect("expected error");
+        assert_eq!(err.lo.col, 13);
+    }
+
+    #[test]
+    fn parse_with_bom() {
+        let (spec, tokens) =
+            Spec::parse_with_tokens(Options::default(), b"\xEF\xBB\xBF## file: a\nhello").unwrap();
+
+        let item = spec.iter().next().expect("expected item");
+        assert_eq!(item.get_param("file"), Some("a"));
+        assert_eq!(tokens[0].lo.col, 3);
+        assert_eq!(tokens[0].lo.byte, 6);
+    }
+
+    #[test]
+    fn rewrite_text_through_ast() {
+        let spec = Spec::parse(Options::default(), b"## file: a\nHello\n..").unwrap();
+
+        let mut ast = spec.into_ast();
+        for item in &mut ast.items {
+            for token in &mut item.template {
+                if let Match::Text(ref mut text) = *token {
+                    *text = text.to_uppercase();
+                }
+            }
+        }
+        ast.items.push(ast::Item {
+            params: vec![],
+            template: vec![Match::Text("extra".into())],
+            span: (FilePosition::new(), FilePosition::new()),
+            origin: None,
+        });
+        let spec = Spec::from_ast(ast);
+
+        assert_eq!(spec.iter().count(), 2);
+        let item = spec.iter().next().unwrap();
+        let params = HashMap::new();
+        item.match_contents(&mut Cursor::new(&b"HELLO\nanything"[..]), &params)
+            .expect("expected match");
+    }
+
+    #[test]
+    fn case_insensitive_param_keys() {
+        let options = Options {
+            case_insensitive_keys: true,
+            ..Options::default()
+        };
+        let spec = Spec::parse(options, b"## File: a\n## FILE: b\nhello").unwrap();
+
+        let item = spec.iter().next().unwrap();
+        assert_eq!(item.get_param("file"), Some("a"));
+        assert_eq!(item.get_params("fIlE"), vec!["a", "b"]);
+        assert_eq!(spec.iter_item_values("file").count(), 1);
+    }
+
+    #[test]
+    fn param_keys_are_case_sensitive_by_default() {
+        let spec = Spec::parse(Options::default(), b"## File: a\nhello").unwrap();
+
+        let item = spec.iter().next().unwrap();
+        assert_eq!(item.get_param("file"), None);
+        assert_eq!(item.get_param("File"), Some("a"));
+        assert_eq!(spec.iter_item_values("file").count(), 0);
+    }
+
+    #[test]
+    fn merge_appends_items() {
+        let mut spec = Spec::parse(Options::default(), b"## file: a\nhello").unwrap();
+        let other = Spec::parse(Options::default(), b"## file: b\nworld\n## file: c\n..").unwrap();
+        spec.merge(other);
+
+        let files: Vec<_> = spec.iter_item_values("file").map(|(_, v)| v).collect();
+        assert_eq!(files, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn iter_items_with_param_prefix() {
+        let spec = Spec::parse(
+            Options::default(),
+            b"## file: output/css/a.css
+a
+## file: output/js/b.js
+b
+## other: output/css/c.css
+c
+## file: output/css/d.css
+d",
+        ).unwrap();
+
+        let files: Vec<_> = spec.iter_items_with_param_prefix("file", "output/css/")
+            .map(|(item, v)| (item.template.len(), v))
+            .collect();
+        assert_eq!(files, vec![(1, "output/css/a.css"), (1, "output/css/d.css")]);
+        assert_eq!(spec.iter_items_with_param_prefix("file", "").count(), 3);
+    }
+
+    #[test]
+    fn from_specs_keeps_order() {
+        let spec = Spec::from_specs(vec![
+            Spec::parse(Options::default(), b"## file: a\nhello").unwrap(),
+            Spec::parse(Options::default(), b"## file: b\nworld").unwrap(),
+        ]);
+
+        let files: Vec<_> = spec.iter_item_values("file").map(|(_, v)| v).collect();
+        assert_eq!(files, vec!["a", "b"]);
+        assert_eq!(Spec::from_specs(Vec::new()).iter().count(), 0);
+    }
+
+    #[test]
+    fn item_iter_len_and_rev() {
+        let spec = Spec::parse(Options::default(), b"## file: a\nx\n## file: b\ny\n## file: c\nz")
+            .unwrap();
+
+        let mut iter = spec.iter();
+        assert_eq!(iter.len(), 3);
+        iter.next();
+        assert_eq!(iter.len(), 2);
+
+        let files: Vec<_> = spec.iter().rev().map(|i| i.get_param("file").unwrap()).collect();
+        assert_eq!(files, vec!["c", "b", "a"]);
+    }
+
+    #[test]
+    fn find_items_by_param() {
+        let spec = Spec::parse(
+            Options::default(),
+            b"## file: a\nx\n## file: b\ny\n## file: a\n## mode: strict\nz",
+        ).unwrap();
+
+        let item = spec.find("file", "a").expect("expected item");
+        assert_eq!(item.template, &[Match::Text("x".into())][..]);
+        assert_eq!(spec.find_all("file", "a").count(), 2);
+        assert_eq!(spec.find_all("file", "b").count(), 1);
+        assert!(spec.find("file", "c").is_none());
+        assert!(spec.find("mode", "a").is_none());
+    }
+
+    #[test]
+    fn parse_many_splits_on_separator_lines() {
+        let specs = Spec::parse_many(
+            Options::default(),
+            b"## file: a\nhello\n---\n## file: b\r\n---\r\nbye",
+            "---",
+        ).unwrap();
+        assert_eq!(specs.len(), 3);
+        assert_eq!(specs[0].find("file", "a").map(|i| i.template.len()), Some(1));
+        assert_eq!(specs[1].iter().next().unwrap().get_param("file"), Some("b"));
+        assert_eq!(
+            specs[2].iter().next().unwrap().template,
+            &[Match::Text("bye".into())][..]
+        );
+    }
+
+    #[test]
+    fn parse_many_error_points_to_original_line() {
+        let err = Spec::parse_many(
+            Options::default(),
+            b"## file: a\nhello\n---\nok\n## file: \"b\n",
+            "---",
+        ).err()
+            .expect("expected error");
+        assert_eq!((err.lo.line, err.lo.col, err.lo.byte), (4, 9, 33));
+    }
+
+    #[test]
+    fn missing_vars() {
+        let spec = Spec::parse(
+            Options::default(),
+            b"## file: a\n${a} ${b} ${a}\n## file: b\n${c} ${b}",
+        ).unwrap();
+        let params = [("b", "x")];
+
+        let item = spec.iter().next().unwrap();
+        assert_eq!(item.missing_vars(&params[..]), vec!["a"]);
+        assert_eq!(spec.missing_vars(&params[..]), vec!["a", "c"]);
+        assert!(spec.missing_vars(&[("a", ""), ("b", ""), ("c", "")][..]).is_empty());
+    }
+
+    #[test]
+    fn vars() {
+        let spec = Spec::parse(
+            Options::default(),
+            b"## file: a\n${b} ${a} ${b}\n## file: b\n${c}",
+        ).unwrap();
+
+        let item = spec.iter().next().unwrap();
+        assert_eq!(item.vars().collect::<Vec<_>>(), vec!["b", "a", "b"]);
+        assert_eq!(spec.vars().into_iter().collect::<Vec<_>>(), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn item_source_span() {
+        let contents = b"## file: a\nhello\n\n## file: b\nbye\n";
+        let spec = Spec::parse(Options::default(), contents).unwrap();
+        let spans: Vec<_> = spec
+            .iter()
+            .map(|item| {
+                let (lo, hi) = item.source_span();
+                (lo.line, hi.line)
+            })
+            .collect();
+        assert_eq!(spans, vec![(0, 2), (3, 4)]);
+    }
+
+    #[test]
+    fn display_error_with_kept_source() {
+        let spec = Spec::parse_with_source(Options::default(), b"## file: a\nhi").unwrap();
+        assert_eq!(spec.source(), Some(&b"## file: a\nhi"[..]));
+
+        let (_, hi) = spec.iter().next().unwrap().source_span();
+        let lo = FilePosition {
+            line: hi.line,
+            col: 0,
+            byte: hi.byte - hi.col,
+        };
+        let err = ParseError::EmptyItem.at(lo, hi);
+        assert_eq!(
+            spec.display_error(&err),
+            "in \"<spec>\"
+1 | ## file: a
+2 | hi
+  | ^^
+  | Expected key after marker"
+        );
+
+        let spec = Spec::parse(Options::default(), b"## file: a\nhi").unwrap();
+        assert_eq!(spec.source(), None);
+        assert_eq!(spec.display_error(&err), err.to_string());
+    }
+
+    #[test]
+    fn duplicate_params() {
+        let spec = Spec::parse(
+            Options::default(),
+            b"## file: a
+## other
+## file: b
+## other: c
+## single: d
+text",
+        ).unwrap();
+
+        let item = spec.iter().next().unwrap();
+        let duplicates: Vec<_> = item.duplicate_params()
+            .into_iter()
+            .map(|(key, spans)| {
+                let spans: Vec<_> = spans
+                    .iter()
+                    .map(|&(lo, hi)| (lo.line, lo.col, hi.col))
+                    .collect();
+                (key, spans)
+            })
+            .collect();
+        assert_eq!(
+            duplicates,
+            vec![
+                ("file", vec![(0, 3, 10), (2, 3, 10)]),
+                ("other", vec![(1, 3, 8), (3, 3, 11)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn match_display_writes_template() {
+        let template = [
+            Match::Indent(2),
+            Match::Text("Hello ".into()),
+            Match::Var("name".into()),
+            Match::NewLine,
+            Match::MultipleLines,
+            Match::NewLine,
+            Match::CaptureLines("rest".into()),
+        ];
+        let written: String = template.iter().map(|m| m.to_string()).collect();
+        assert_eq!(written, "  Hello ${name}\n..\n${..rest}");
+    }
+
+    #[test]
+    fn match_display_gap_differs_from_skip() {
+        assert_eq!(Match::Gap(3).to_string(), "<gap 3>");
+        assert_ne!(Match::Gap(3).to_string(), Match::MultipleLines.to_string());
+    }
+
+    #[test]
+    fn match_parse_line() {
+        let line = Match::parse_line(Options::default(), "Hello ${ name }!").unwrap();
+        assert_eq!(
+            line,
+            vec![
+                Match::Text("Hello ".into()),
+                Match::Var("name".into()),
+                Match::Text("!".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn match_parse_line_with_escapes() {
+        let options = Options {
+            interpret_escapes: true,
+            ..Options::default()
+        };
+        let line = Match::parse_line(options, "a\\tb").unwrap();
+        assert_eq!(line, vec![Match::Text("a\tb".into())]);
+    }
+
+    #[test]
+    fn match_parse_line_rejects_params() {
+        let err = Match::parse_line(Options::default(), "## file: a").unwrap_err();
+        match err.desc {
+            ParseError::ExpectedDifferentToken { .. } => (),
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+
+    #[test]
+    fn multiline_param_value() {
+        let spec = Spec::parse(
+            Options::default(),
+            b"## description: first line
+##: \"  second line\"
+## file: a
+hello",
+        ).unwrap();
+        let item = spec.iter().next().unwrap();
+
+        let value = item.get_param("description").unwrap();
+        assert_eq!(value, "first line\n  second line");
+        assert_eq!(item.get_param("file"), Some("a"));
+        assert_eq!(item.template, &[Match::Text("hello".into())][..]);
+
+        let written: Vec<_> = value.lines().map(|line| format!("\"{}\"", line)).collect();
+        let rewritten = format!("## description: {}\nhello", written.join("\n##: "));
+        let spec = Spec::parse(Options::default(), rewritten.as_bytes()).unwrap();
+        let item = spec.iter().next().unwrap();
+        assert_eq!(item.get_param("description"), Some(value));
+    }
+
+    #[test]
+    fn empty_key_without_value_before_is_a_param() {
+        let spec = Spec::parse(
+            Options::default(),
+            b"## file
+##: a
+hello
+##: b",
+        ).unwrap();
+        let items: Vec<_> = spec.iter().collect();
+
+        assert_eq!(items[0].get_param("file"), None);
+        assert_eq!(items[0].get_param(""), Some("a"));
+        assert_eq!(items[1].get_param(""), Some("b"));
+    }
+
+    #[test]
+    fn parse_line_range() {
+        let spec = Spec::parse(
+            Options::default(),
+            b"..^10
+fn main() {
+..",
+        ).unwrap();
+        let item = spec.iter().next().unwrap();
+
+        assert_eq!(
+            item.template,
+            &[
+                Match::LineRange { max: 10 },
+                Match::Text("fn main() {".into()),
+                Match::MultipleLines,
+            ][..]
+        );
+        assert_eq!(item.template[0].to_string(), "..^10");
+    }
+
+    #[test]
+    fn line_range_requires_number() {
+        let err = Spec::parse(Options::default(), b"..^\nfn main() {").unwrap_err();
+        assert_eq!(err.desc, ParseError::Lex(LexError::ExpectedLineCount));
+        assert_eq!((err.lo.line, err.lo.col), (0, 3));
+
+        let err = Spec::parse(Options::default(), b"..^99999999999999999999999\na").unwrap_err();
+        assert_eq!(err.desc, ParseError::Lex(LexError::ExpectedLineCount));
+    }
+}
@@ -0,0 +1,89 @@
+extern crate specker;
+
+mod support;
+
+#[cfg(test)]
+mod report {
+    use specker::{Error, Match, MatchSummary, Options, Spec};
+    use std::io;
+    use std::path::PathBuf;
+    use support::{match_item, new_item};
+
+    #[test]
+    fn summary_counts_passed_and_failed_files() {
+        let template = &[Match::Text("hello".into())];
+
+        let mut summary = MatchSummary::new();
+        summary.record("a.txt", match_item(new_item(template), &[], "hello"));
+        summary.record("b.txt", match_item(new_item(template), &[], "world"));
+        summary.record("c.txt", match_item(new_item(template), &[], "hello"));
+
+        assert_eq!(summary.passed, 2);
+        assert_eq!(summary.failed(), 1);
+        assert_eq!(summary.total(), 3);
+        assert!(!summary.is_success());
+        assert_eq!(summary.failures[0].0.to_str(), Some("b.txt"));
+        assert!(summary.to_string().ends_with("2 passed, 1 failed"));
+        assert!(summary.to_string().starts_with("b.txt: "));
+    }
+
+    #[test]
+    fn summary_to_json() {
+        let template = &[Match::Text("\"hi\"".into())];
+
+        let mut summary = MatchSummary::new();
+        summary.record("ok.txt", match_item(new_item(template), &[], "\"hi\""));
+        summary.record("bad.txt", match_item(new_item(template), &[], ""));
+
+        let json = summary.to_json();
+        assert!(json.starts_with(
+            "{\"passed\":1,\"failed\":1,\"failures\":[{\"path\":\"bad.txt\",\"line\":0,\"col\":0,"
+        ));
+        assert!(json.ends_with(r#""message":"Expected \"\\\"hi\\\"\", found \"\""}]}"#));
+    }
+
+    #[test]
+    fn empty_summary_is_success() {
+        let summary = MatchSummary::new();
+        assert!(summary.is_success());
+        assert_eq!(summary.to_string(), "0 passed, 0 failed");
+        assert_eq!(summary.to_json(), "{\"passed\":0,\"failed\":0,\"failures\":[]}");
+    }
+
+    #[test]
+    fn parse_error_report_keeps_path_and_positions() {
+        let options = Options {
+            skip_lines: "..",
+            marker: "##",
+            var_start: "${",
+            var_end: "}",
+            param_separator: ":",
+            escape: None,
+            allow_indented_markers: false,
+            case_insensitive_keys: false,
+            interpret_escapes: false,
+        };
+        let err = Spec::parse(options, b"## file: \"a\n").err().unwrap();
+        let (lo, hi) = (err.lo, err.hi);
+        let err = Error::from((PathBuf::from("spec.txt"), err));
+        let message = err.to_string();
+
+        let report = err.into_report();
+        let cloned = report.clone();
+        assert_eq!(cloned, report);
+        assert_eq!(report.message, message);
+        assert_eq!(report.to_string(), message);
+        assert_eq!(report.path, Some(PathBuf::from("spec.txt")));
+        assert_eq!(report.span, Some((lo, hi)));
+    }
+
+    #[test]
+    fn io_error_report_keeps_message() {
+        let err = Error::from(io::Error::new(io::ErrorKind::NotFound, "gone"));
+
+        let report = err.into_report();
+        assert_eq!(report.message, "gone");
+        assert_eq!(report.path, None);
+        assert_eq!(report.span, None);
+    }
+}
@@ -0,0 +1,88 @@
+extern crate specker;
+
+mod support;
+
+#[cfg(test)]
+mod skip_at_least_one_line {
+    use specker::{At, Match, MatchOptions, TemplateMatchError};
+    use std::collections::HashMap;
+    use support::new_item;
+
+    fn match_non_empty(template: &[Match], contents: &str) -> Result<(), At<TemplateMatchError>> {
+        let options = MatchOptions {
+            skip_at_least_one_line: true,
+            ..MatchOptions::default()
+        };
+        let mut cursor = ::std::io::Cursor::new(contents.as_bytes());
+        new_item(template).match_contents_with(&mut cursor, &HashMap::new(), &options)
+    }
+
+    fn section() -> Vec<Match> {
+        vec![
+            Match::Text("begin".into()),
+            Match::MultipleLines,
+            Match::Text("end".into()),
+        ]
+    }
+
+    #[test]
+    fn empty_section_fails() {
+        let err = match_non_empty(&section(), "begin\nend")
+            .err()
+            .expect("expected error");
+        err.assert_matches(&TemplateMatchError::ExpectedSkippedLine, (1, 0), (1, 0))
+            .unwrap();
+    }
+
+    #[test]
+    fn missing_end_is_not_reported_as_empty_section() {
+        let err = match_non_empty(&section(), "begin\nbody")
+            .err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::ExpectedTextFoundEof {
+                expected: "end".into(),
+                found: "body".into(),
+            },
+            (1, 4),
+            (1, 4),
+        ).unwrap();
+    }
+
+    #[test]
+    fn section_with_lines_matches() {
+        match_non_empty(&section(), "begin\nbody\nend").expect("expected match");
+        match_non_empty(&section(), "begin\nbody\nmore\nend").expect("expected match");
+    }
+
+    #[test]
+    fn empty_trailing_skip_fails() {
+        let template = &[Match::Text("begin".into()), Match::MultipleLines];
+
+        match_non_empty(template, "begin\nbody").expect("expected match");
+        let err = match_non_empty(template, "begin\n")
+            .err()
+            .expect("expected error");
+        err.assert_matches(&TemplateMatchError::ExpectedSkippedLine, (1, 0), (1, 0))
+            .unwrap();
+    }
+
+    #[test]
+    fn empty_leading_skip_fails() {
+        let template = &[Match::MultipleLines, Match::Text("end".into())];
+
+        match_non_empty(template, "body\nend").expect("expected match");
+        let err = match_non_empty(template, "")
+            .err()
+            .expect("expected error");
+        err.assert_matches(&TemplateMatchError::ExpectedSkippedLine, (0, 0), (0, 0))
+            .unwrap();
+    }
+
+    #[test]
+    fn empty_section_matches_by_default() {
+        new_item(&section())
+            .match_bytes(b"begin\nend", &HashMap::new())
+            .expect("expected match");
+    }
+}
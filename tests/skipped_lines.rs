@@ -0,0 +1,70 @@
+extern crate specker;
+
+mod support;
+
+#[cfg(test)]
+mod skipped_lines {
+    use specker::{Match, TemplateMatchError};
+    use std::collections::HashMap;
+    use support::new_item;
+
+    fn skipped_lines(template: &[Match], contents: &str) -> Vec<usize> {
+        new_item(template)
+            .match_skipped_lines(&mut contents.as_bytes(), &HashMap::new())
+            .expect("expected match")
+    }
+
+    #[test]
+    fn counts_lines_of_every_skip() {
+        let template = [
+            Match::MultipleLines,
+            Match::Text("body".into()),
+            Match::MultipleLines,
+        ];
+        assert_eq!(skipped_lines(&template, "a\nb\nc\nbody\nd\ne"), vec![3, 2]);
+        assert_eq!(skipped_lines(&template, "body\n"), vec![0, 0]);
+    }
+
+    #[test]
+    fn trailing_skip_counts_final_line_ending() {
+        let template = [Match::Text("body".into()), Match::MultipleLines];
+        assert_eq!(skipped_lines(&template, "body\r\nd\r\ne\r\n"), vec![2]);
+    }
+
+    #[test]
+    fn first_of_consecutive_skips_takes_all_lines() {
+        let template = [
+            Match::MultipleLines,
+            Match::MultipleLines,
+            Match::Text("body".into()),
+        ];
+        assert_eq!(skipped_lines(&template, "a\nb\nbody"), vec![2, 0]);
+    }
+
+    #[test]
+    fn capture_lines_are_counted() {
+        let template = [
+            Match::Text("start".into()),
+            Match::CaptureLines("body".into()),
+            Match::Text("end".into()),
+        ];
+        assert_eq!(skipped_lines(&template, "start\na\nend"), vec![1]);
+    }
+
+    #[test]
+    fn mismatch_is_an_error() {
+        let err = new_item(&[Match::Text("a".into())])
+            .match_skipped_lines(&mut &b"b"[..], &HashMap::new())
+            .err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::ExpectedText {
+                expected: "a".into(),
+                found: "b".into(),
+                found_line: "b".into(),
+            },
+            (0, 0),
+            (0, 1),
+        ).unwrap();
+    }
+}
@@ -0,0 +1,60 @@
+#![cfg(feature = "insta")]
+
+extern crate specker;
+
+mod support;
+
+#[cfg(test)]
+mod snapshot {
+    use specker::snapshot::{error_snapshot, item_snapshot};
+    use specker::Match;
+    use std::collections::HashMap;
+    use support::{match_item, new_item};
+
+    #[test]
+    fn snapshot_of_writable_item_is_written_contents() {
+        let tokens = &[
+            Match::Text("hello ".into()),
+            Match::Var("name".into()),
+            Match::NewLine,
+            Match::Text("world".into()),
+            Match::NewLine,
+        ];
+
+        let mut params = HashMap::new();
+        params.insert("name", "there");
+
+        assert_eq!(item_snapshot(&new_item(tokens), &params), "hello there\nworld\n");
+    }
+
+    #[test]
+    fn snapshot_keeps_missing_vars_and_skips() {
+        let tokens = &[
+            Match::Text("hello ".into()),
+            Match::Var("name".into()),
+            Match::NewLine,
+            Match::MultipleLines,
+            Match::Text("end".into()),
+        ];
+
+        assert_eq!(
+            item_snapshot(&new_item(tokens), &HashMap::new()),
+            "hello ${name}\n..\nend"
+        );
+    }
+
+    #[test]
+    fn error_snapshot_redacts_param_values() {
+        let tokens = &[Match::Text("path ".into()), Match::Var("dir".into())];
+
+        let err = match_item(new_item(tokens), &[("dir", "/tmp/x1")], "path /tmp/x2")
+            .expect_err("expected mismatch");
+
+        let mut params = HashMap::new();
+        params.insert("dir", "/tmp/x1");
+        let message = error_snapshot(&err, &params);
+
+        assert_eq!(message, err.to_string().replace("/tmp/x1", "${dir}"));
+        assert!(!message.contains("/tmp/x1"));
+    }
+}
@@ -0,0 +1,372 @@
+#![cfg(feature = "walk")]
+
+extern crate specker;
+
+#[cfg(test)]
+mod spec_path {
+    use specker::{
+        parse_specs_from_map, run_spec, run_spec_in_map, walk_spec_dir, walk_spec_dir_collect,
+        walk_spec_dir_ext, walk_spec_dir_filtered, Error, Options, Spec, SpecPath,
+        TemplateMatchError, TemplateWriteError,
+    };
+    use std::collections::HashMap;
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join("specker-tests").join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn spec_path(contents: &str) -> SpecPath {
+        SpecPath {
+            spec: Spec::parse(Options::default(), contents.as_bytes()).unwrap(),
+            path: PathBuf::from("spec.txt"),
+        }
+    }
+
+    #[test]
+    fn item_with_two_files() {
+        let spec_path = spec_path(
+            "## file: a.txt
+## file: b.txt
+hello",
+        );
+
+        let items = spec_path.items_with_all_files();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].1, vec!["a.txt", "b.txt"]);
+    }
+
+    #[test]
+    fn run_spec_reports_outcome_per_item_and_file() {
+        let dir = temp_dir("run_spec_reports_outcome_per_item_and_file");
+        fs::write(dir.join("a.txt"), "hello").unwrap();
+        fs::write(dir.join("b.txt"), "bye").unwrap();
+
+        let spec_path = spec_path(
+            "## file: a.txt
+## file: b.txt
+hello
+## file: b.txt
+## name: second
+bye",
+        );
+        let outcomes = run_spec(&spec_path, &dir, &HashMap::new());
+        assert_eq!(outcomes.len(), 3);
+
+        assert_eq!(outcomes[0].file, "a.txt");
+        assert_eq!(outcomes[0].path, dir.join("a.txt"));
+        assert!(outcomes[0].result.is_ok());
+
+        assert_eq!(outcomes[1].file, "b.txt");
+        assert!(outcomes[1].result.is_err());
+
+        assert_eq!(outcomes[2].file, "b.txt");
+        assert_eq!(outcomes[2].item.get_param("name"), Some("second"));
+        assert!(outcomes[2].result.is_ok());
+    }
+
+    #[test]
+    fn every_listed_file_must_match() {
+        let dir = temp_dir("every_listed_file_must_match");
+        fs::write(dir.join("a.txt"), "hello").unwrap();
+        fs::write(dir.join("b.txt"), "hello").unwrap();
+
+        let spec_path = spec_path(
+            "## file: a.txt
+## file: b.txt
+hello",
+        );
+        assert!(spec_path.match_all_files(&dir, &HashMap::new()).is_empty());
+
+        fs::write(dir.join("b.txt"), "bye").unwrap();
+        let errors = spec_path.match_all_files(&dir, &HashMap::new());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, dir.join("b.txt"));
+        errors[0]
+            .1
+            .assert_matches(
+                &TemplateMatchError::ExpectedText {
+                    expected: "hello".into(),
+                    found: "bye".into(),
+                    found_line: "bye".into(),
+                },
+                (0, 0),
+                (0, 3),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn walk_stops_at_max_depth() {
+        let root = temp_dir("walk_stops_at_max_depth");
+        let mut dir = root.clone();
+        for i in 0..6 {
+            dir = dir.join(format!("d{}", i));
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("spec.txt"), "hello").unwrap();
+        }
+
+        let results: Vec<_> = walk_spec_dir(&root, "txt", Options::default())
+            .max_depth(3)
+            .collect();
+
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.is_ok()));
+    }
+
+    #[test]
+    fn walk_is_not_limited_by_default() {
+        let root = temp_dir("walk_is_not_limited_by_default");
+        let mut dir = root.clone();
+        for i in 0..6 {
+            dir = dir.join(format!("d{}", i));
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("spec.txt"), "hello").unwrap();
+        }
+
+        let results: Vec<_> = walk_spec_dir(&root, "txt", Options::default()).collect();
+        assert_eq!(results.len(), 6);
+        assert!(results.iter().all(|r| r.is_ok()));
+    }
+
+    #[test]
+    fn collect_returns_all_parse_errors() {
+        let root = temp_dir("collect_returns_all_parse_errors");
+        fs::write(root.join("a.txt"), "## file: a\nhello").unwrap();
+        fs::write(root.join("b.txt"), "## file: \"b").unwrap();
+        fs::write(root.join("c.txt"), "## file: c\nhello").unwrap();
+        fs::write(root.join("d.txt"), "## file: \"d").unwrap();
+
+        let (specs, errors) = walk_spec_dir_collect(&root, "txt", Options::default());
+
+        assert_eq!(specs.len(), 2);
+        assert_eq!(errors.len(), 2);
+        for e in errors {
+            match e {
+                Error::Parse { .. } => (),
+                other => panic!("expected parse error but got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn walk_multiple_extensions() {
+        let root = temp_dir("walk_multiple_extensions");
+        fs::write(root.join("a.txt"), "hello").unwrap();
+        fs::write(root.join("b.spec"), "hello").unwrap();
+        fs::write(root.join("c.SPEC"), "hello").unwrap();
+        fs::write(root.join("d.md"), "hello").unwrap();
+
+        let mut names: Vec<_> = walk_spec_dir_ext(&root, &["txt", "spec"], Options::default())
+            .map(|r| r.unwrap().path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+
+        assert_eq!(names, vec!["a.txt", "b.spec", "c.SPEC"]);
+    }
+
+    #[test]
+    fn sorted_walk_returns_specs_in_path_order() {
+        let root = temp_dir("sorted_walk_returns_specs_in_path_order");
+        fs::create_dir_all(root.join("b")).unwrap();
+        for name in &["c.txt", "a.txt", "b/z.txt", "b/a.txt", "ba.txt"] {
+            fs::write(root.join(name), "hello").unwrap();
+        }
+
+        let paths: Vec<_> = walk_spec_dir(&root, "txt", Options::default())
+            .sorted()
+            .map(|r| r.unwrap().path.strip_prefix(&root).unwrap().to_path_buf())
+            .collect();
+
+        let mut sorted = paths.clone();
+        sorted.sort();
+        assert_eq!(paths, sorted);
+        assert_eq!(paths.len(), 5);
+    }
+
+    #[test]
+    fn depth_limited_walk_ignores_deeper_files() {
+        let root = temp_dir("depth_limited_walk_ignores_deeper_files");
+        fs::create_dir_all(root.join("a").join("b")).unwrap();
+        fs::write(root.join("top.txt"), "hello").unwrap();
+        fs::write(root.join("a").join("mid.txt"), "hello").unwrap();
+        // would fail to parse if it was read
+        fs::write(root.join("a").join("b").join("deep.txt"), "## file: \"a").unwrap();
+
+        let names: Vec<_> = walk_spec_dir(&root, "txt", Options::default())
+            .max_depth(2)
+            .sorted()
+            .map(|r| r.unwrap().path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+
+        assert_eq!(names, vec!["mid.txt", "top.txt"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn walk_follows_links_only_when_asked() {
+        use std::os::unix::fs::symlink;
+
+        let root = temp_dir("walk_follows_links_only_when_asked");
+        let shared = root.join("shared");
+        let specs = root.join("specs");
+        fs::create_dir_all(&shared).unwrap();
+        fs::create_dir_all(&specs).unwrap();
+        fs::write(shared.join("common.txt"), "hello").unwrap();
+        symlink(&shared, specs.join("linked")).unwrap();
+
+        let not_followed = walk_spec_dir(&specs, "txt", Options::default()).follow_links(false);
+        assert_eq!(not_followed.count(), 0);
+
+        let paths: Vec<_> = walk_spec_dir(&specs, "txt", Options::default())
+            .follow_links(true)
+            .map(|r| r.unwrap().path)
+            .collect();
+        assert_eq!(paths, vec![specs.join("linked").join("common.txt")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn walk_reports_link_loop() {
+        use std::os::unix::fs::symlink;
+
+        let root = temp_dir("walk_reports_link_loop");
+        fs::create_dir_all(root.join("a")).unwrap();
+        symlink(&root, root.join("a").join("loop")).unwrap();
+
+        let errors: Vec<_> = walk_spec_dir(&root, "txt", Options::default())
+            .follow_links(true)
+            .filter_map(|r| r.err())
+            .collect();
+        assert_eq!(errors.len(), 1);
+        match errors[0] {
+            Error::WalkDir(ref e) => assert!(e.loop_ancestor().is_some()),
+            ref other => panic!("expected walk error but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn walk_skips_filtered_dirs_and_files() {
+        let root = temp_dir("walk_skips_filtered_dirs_and_files");
+        fs::create_dir_all(root.join("target")).unwrap();
+        fs::create_dir_all(root.join("specs")).unwrap();
+        // would fail to parse if it was read
+        fs::write(root.join("target").join("broken.txt"), "## file: \"a").unwrap();
+        fs::write(root.join("specs").join("a.txt"), "hello").unwrap();
+        fs::write(root.join("specs").join("skip.txt"), "hello").unwrap();
+
+        let root_clone = root.clone();
+        let paths: Vec<_> = walk_spec_dir_filtered(&root, "txt", Options::default(), move |p| {
+            p != root_clone.join("target") && p.file_name().map_or(true, |n| n != "skip.txt")
+        }).map(|r| r.unwrap().path)
+            .collect();
+
+        assert_eq!(paths, vec![root.join("specs").join("a.txt")]);
+    }
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    fn assert_sync<T: Sync>(_: &T) {}
+
+    #[test]
+    fn spec_path_and_walk_can_be_sent_to_threads() {
+        let spec_path = spec_path("## file: a.txt\nhello");
+        assert_send(&spec_path);
+        assert_sync(&spec_path);
+
+        let dir = temp_dir("spec_path_and_walk_can_be_sent_to_threads");
+        fs::write(dir.join("a.txt"), "## file: a\nhello").unwrap();
+        let iter = walk_spec_dir_filtered(&dir, "txt", Options::default(), |_| true);
+        assert_send(&iter);
+
+        let count = ::std::thread::spawn(move || iter.count()).join().unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn parse_and_run_specs_from_map() {
+        let mut files = HashMap::new();
+        files.insert(PathBuf::from("spec/b.txt"), b"## file: out/b\nbye".to_vec());
+        files.insert(PathBuf::from("spec/a.TXT"), b"## file: out/a\nhello".to_vec());
+        files.insert(PathBuf::from("spec/c.md"), b"not a spec".to_vec());
+        files.insert(PathBuf::from("out/a"), b"hello".to_vec());
+
+        let specs = parse_specs_from_map(&files, "txt", Options::default());
+        let paths: Vec<_> = specs
+            .iter()
+            .map(|s| s.as_ref().unwrap().path.clone())
+            .collect();
+        assert_eq!(paths, vec![PathBuf::from("spec/a.TXT"), PathBuf::from("spec/b.txt")]);
+
+        let base_dir = PathBuf::new();
+        let params = HashMap::new();
+        let a = run_spec_in_map(specs[0].as_ref().unwrap(), &files, &base_dir, &params);
+        assert_eq!(a.len(), 1);
+        assert!(a[0].result.is_ok());
+
+        let b = run_spec_in_map(specs[1].as_ref().unwrap(), &files, &base_dir, &params);
+        assert_eq!(b[0].path, PathBuf::from("out/b"));
+        match b[0].result {
+            Err(ref e) => match e.desc {
+                TemplateMatchError::Io(ref e) => {
+                    assert_eq!(e.kind(), ::std::io::ErrorKind::NotFound)
+                }
+                ref other => panic!("expected io error, got {:?}", other),
+            },
+            Ok(()) => panic!("expected error"),
+        }
+    }
+
+    #[test]
+    fn parse_specs_from_map_returns_parse_errors() {
+        let mut files = HashMap::new();
+        files.insert(PathBuf::from("bad.txt"), b"## file: \"a".to_vec());
+
+        let specs = parse_specs_from_map(&files, "txt", Options::default());
+        match specs[0] {
+            Err(Error::Parse { ref path, .. }) => assert_eq!(path, &PathBuf::from("bad.txt")),
+            ref other => panic!("expected parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn write_all_writes_file_params() {
+        let dir = temp_dir("write_all_writes_file_params");
+        let spec = Spec::parse(
+            Options::default(),
+            b"## file: a.txt
+## file: nested/b.txt
+hello ${name}
+## other: c.txt
+not written",
+        ).unwrap();
+
+        spec.write_all(&dir, &[("name", "world")][..]).unwrap();
+
+        assert_eq!(fs::read_to_string(dir.join("a.txt")).unwrap(), "hello world");
+        assert_eq!(fs::read_to_string(dir.join("nested/b.txt")).unwrap(), "hello world");
+        assert!(!dir.join("c.txt").exists());
+    }
+
+    #[test]
+    fn write_to_path_does_not_create_file_on_error() {
+        let dir = temp_dir("write_to_path_does_not_create_file_on_error");
+        let spec = Spec::parse(Options::default(), b"## file: a.txt\nhello ${name}").unwrap();
+        let path = dir.join("nested/a.txt");
+
+        let err = spec.iter()
+            .next()
+            .unwrap()
+            .write_to_path(&path, &[][..])
+            .err()
+            .expect("expected error");
+
+        assert_eq!(err, TemplateWriteError::MissingParam("name".into()));
+        assert!(!path.exists());
+    }
+}
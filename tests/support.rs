@@ -3,7 +3,7 @@
 
 extern crate specker;
 
-use specker::{At, TemplateMatchError, TemplateWriteError};
+use specker::{At, MatchOptions, TemplateMatchError, TemplateWriteError};
 
 macro_rules! assert_contents {
     ($a:expr, $b:expr) => {
@@ -12,10 +12,7 @@ macro_rules! assert_contents {
 }
 
 pub fn new_item<'a>(match_list: &'a [specker::Match]) -> specker::Item<'a> {
-    specker::Item {
-        params: &[],
-        template: match_list,
-    }
+    specker::Item::new(&[], match_list)
 }
 
 pub fn match_item<'a>(
@@ -24,7 +21,35 @@ pub fn match_item<'a>(
     contents: &str,
 ) -> Result<(), At<TemplateMatchError>> {
     let mut cursor = ::std::io::Cursor::new(contents.as_bytes());
-    Ok(item.match_contents(&mut cursor, &params.iter().cloned().collect())?)
+    Ok(item.match_contents(&mut cursor, params)?)
+}
+
+pub fn match_with(
+    template: &[specker::Match],
+    contents: &str,
+    options: &MatchOptions,
+) -> Result<(), At<TemplateMatchError>> {
+    match_with_params(template, &[], contents, options)
+}
+
+pub fn match_with_params(
+    template: &[specker::Match],
+    params: &[(&str, &str)],
+    contents: &str,
+    options: &MatchOptions,
+) -> Result<(), At<TemplateMatchError>> {
+    let mut cursor = ::std::io::Cursor::new(contents.as_bytes());
+    new_item(template).match_contents_with(&mut cursor, params, options)
+}
+
+pub fn match_item_against_baseline<'a>(
+    item: specker::Item<'a>,
+    params: &[(&str, &str)],
+    contents: &str,
+    baseline: &[At<TemplateMatchError>],
+) -> Vec<At<TemplateMatchError>> {
+    let mut cursor = ::std::io::Cursor::new(contents.as_bytes());
+    item.match_against_baseline(&mut cursor, params, baseline)
 }
 
 pub fn write<'a>(
@@ -33,7 +58,7 @@ pub fn write<'a>(
 ) -> Result<Vec<u8>, TemplateWriteError> {
     let mut file = Vec::new();
 
-    item.write_contents(&mut file, &params.iter().cloned().collect())?;
+    item.write_contents(&mut file, params)?;
 
     Ok(file)
 }
@@ -0,0 +1,73 @@
+extern crate specker;
+
+mod support;
+
+#[cfg(test)]
+mod trailing_newline {
+    use specker::{At, Match, MatchOptions, TemplateMatchError};
+    use std::collections::HashMap;
+    use std::io::Cursor;
+    use support::new_item;
+
+    fn match_requiring(
+        template: &[Match],
+        contents: &str,
+        trailing_newline: bool,
+    ) -> Result<(), At<TemplateMatchError>> {
+        let options = MatchOptions {
+            require_trailing_newline: Some(trailing_newline),
+            ..MatchOptions::default()
+        };
+        let mut cursor = Cursor::new(contents.as_bytes());
+        new_item(template).match_contents_with(&mut cursor, &HashMap::new(), &options)
+    }
+
+    fn line() -> Vec<Match> {
+        vec![Match::Text("a".into()), Match::NewLine]
+    }
+
+    #[test]
+    fn single_trailing_newline_is_accepted() {
+        match_requiring(&line(), "a\n", true).expect("expected match");
+        match_requiring(&line(), "a\r\n", true).expect("expected match");
+        match_requiring(&[], "", true).expect("expected match");
+    }
+
+    #[test]
+    fn missing_trailing_newline_fails_at_eof() {
+        let err = match_requiring(&[Match::Text("a".into())], "a", true)
+            .err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::WrongTrailingNewline { expected: true },
+            (0, 1),
+            (0, 1),
+        ).unwrap();
+    }
+
+    #[test]
+    fn extra_trailing_newlines_fail() {
+        let err = match_requiring(&line(), "a\n\n\n", true)
+            .err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::WrongTrailingNewline { expected: true },
+            (1, 0),
+            (1, 0),
+        ).unwrap();
+    }
+
+    #[test]
+    fn trailing_newline_can_be_forbidden() {
+        match_requiring(&[Match::Text("a".into())], "a", false).expect("expected match");
+
+        let err = match_requiring(&line(), "a\r\n", false)
+            .err()
+            .expect("expected error");
+        err.assert_matches(
+            &TemplateMatchError::WrongTrailingNewline { expected: false },
+            (0, 1),
+            (0, 1),
+        ).unwrap();
+    }
+}
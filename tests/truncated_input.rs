@@ -0,0 +1,81 @@
+extern crate specker;
+
+mod support;
+
+#[cfg(test)]
+mod truncated_input {
+    use specker::{Match, MatchOptions};
+    use support::new_item;
+
+    fn template() -> Vec<Match> {
+        vec![
+            Match::Text("héllo ".into()),
+            Match::Var("name".into()),
+            Match::MultipleLines,
+            Match::Indent(2),
+            Match::Text("a".into()),
+            Match::Gap(3),
+            Match::Text("b".into()),
+            Match::NewLine,
+            Match::Text("end".into()),
+            Match::NewLine,
+        ]
+    }
+
+    fn option_sets() -> Vec<MatchOptions> {
+        vec![
+            MatchOptions::default(),
+            MatchOptions {
+                normalize_whitespace: true,
+                collapse_blank_lines: true,
+                ..MatchOptions::default()
+            },
+            MatchOptions {
+                anchored: false,
+                skip_at_least_one_line: true,
+                ..MatchOptions::default()
+            },
+            MatchOptions {
+                dedent: true,
+                ignore_final_newline: true,
+                normalize_crlf: true,
+                ..MatchOptions::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn every_truncation_is_an_error_not_a_panic() {
+        let template = template();
+        let item = new_item(&template);
+        let params = [("name", "wörld")];
+        let input = "\u{feff}héllo wörld\r\nskipped\n\n  a..b\r\nend\n".as_bytes();
+
+        for options in &option_sets() {
+            item.match_contents_with(&mut &input[..], &params[..], options)
+                .expect("expected full input to match");
+            for len in 0..input.len() - 1 {
+                let truncated = &input[..len];
+                assert!(
+                    item.match_contents_with(&mut &truncated[..], &params[..], options)
+                        .is_err(),
+                    "expected {:?} to fail",
+                    String::from_utf8_lossy(truncated)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn truncated_template_lines_do_not_panic() {
+        let template = template();
+        let input = "héllo wörld\nskipped\n  a..b\nend\n".as_bytes();
+
+        for template_len in 0..template.len() {
+            let item = new_item(&template[..template_len]);
+            for len in 0..input.len() + 1 {
+                let _ = item.match_contents(&mut &input[..len], &[("name", "wörld")][..]);
+            }
+        }
+    }
+}
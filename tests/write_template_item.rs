@@ -98,4 +98,99 @@ mod write_template_item {
         ).unwrap();
         assert_contents!(&file, "hello\nworld\nand bye world\n.");
     }
+
+    #[test]
+    fn indent() {
+        let file = write(new_item(&[Match::Indent(2), Match::Text("a".into())]), &[]).unwrap();
+        assert_contents!(&file, "  a");
+    }
+
+    #[test]
+    fn write_contents_env_falls_back_to_environment() {
+        ::std::env::set_var("SPECKER_TEST_WRITE_ENV", "from env");
+        let template = [
+            Match::Var("SPECKER_TEST_WRITE_ENV".into()),
+            Match::NewLine,
+            Match::Var("name".into()),
+        ];
+        let mut params = ::std::collections::HashMap::new();
+        params.insert("name", "world");
+
+        let mut file = Vec::new();
+        new_item(&template)
+            .write_contents_env(&mut file, &params)
+            .unwrap();
+        assert_contents!(&file, "from env\nworld");
+
+        let err = write(new_item(&template), &[("name", "world")])
+            .err()
+            .expect("expected error");
+        assert_eq!(
+            err,
+            specker::TemplateWriteError::MissingParam("SPECKER_TEST_WRITE_ENV".into())
+        );
+    }
+
+    #[test]
+    fn write_contents_env_prefers_params() {
+        ::std::env::set_var("SPECKER_TEST_WRITE_ENV_PARAM", "from env");
+        let template = [Match::Var("SPECKER_TEST_WRITE_ENV_PARAM".into())];
+        let mut params = ::std::collections::HashMap::new();
+        params.insert("SPECKER_TEST_WRITE_ENV_PARAM", "from params");
+
+        let mut file = Vec::new();
+        new_item(&template)
+            .write_contents_env(&mut file, &params)
+            .unwrap();
+        assert_contents!(&file, "from params");
+    }
+
+    #[test]
+    fn match_contents_env_falls_back_to_environment() {
+        ::std::env::set_var("SPECKER_TEST_MATCH_ENV", "hello");
+        let template = [Match::Var("SPECKER_TEST_MATCH_ENV".into())];
+
+        new_item(&template)
+            .match_contents_env(&mut &b"hello"[..], &[][..])
+            .expect("expected match");
+    }
+
+    #[test]
+    fn write_and_match_without_params() {
+        let template = [Match::Text("hi".into()), Match::NewLine];
+
+        let mut file = Vec::new();
+        new_item(&template).write_contents_default(&mut file).unwrap();
+        assert_contents!(&file, "hi\n");
+
+        new_item(&template)
+            .match_contents_default(&mut &file[..])
+            .expect("expected match");
+    }
+
+    #[test]
+    fn multiple_lines_are_written_as_placeholder() {
+        let template = [
+            Match::MultipleLines,
+            Match::Text("fn main() {".into()),
+            Match::MultipleLines,
+            Match::Text("}".into()),
+            Match::CaptureLines("rest".into()),
+        ];
+
+        let mut file = Vec::new();
+        new_item(&template)
+            .write_contents_with(&mut file, &[][..], Some("// ..."))
+            .unwrap();
+        assert_contents!(&file, "// ...\nfn main() {\n// ...\n}\n// ...");
+    }
+
+    #[test]
+    fn multiple_lines_without_placeholder_produce_error() {
+        let err = new_item(&[Match::MultipleLines])
+            .write_contents_with(&mut Vec::new(), &[][..], None)
+            .err()
+            .expect("expected error");
+        assert_eq!(err, specker::TemplateWriteError::CanNotWriteMatchAnySymbols);
+    }
 }